@@ -0,0 +1,72 @@
+//! Times the almost locked set searches on grids where the singles are stuck.
+//!
+//! Run with `cargo bench --bench als`.
+
+use cde_rust_sudoku::als::*;
+use cde_rust_sudoku::base::*;
+use cde_rust_sudoku::examples::*;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+const PUZZLES: [(&str, &str); 3] = [
+    ("SUDOKU1", SUDOKU1),
+    ("SUDOKU2", SUDOKU2),
+    ("SUDOKU3", SUDOKU3),
+];
+
+// stuck for all pattern techniques, so every search runs on a grid with many candidates
+const HARD: &str =
+    "8....51..1..6.83.9.7..3..5..5.......3..9.....4...5.6.1.1..6......3.1.47...63..9..";
+
+// every measurement is averaged over up to this many runs, slow ones are only run once
+const RUNS: u32 = 10;
+const MAX_TOTAL: Duration = Duration::from_secs(1);
+
+fn time<F: FnMut() -> usize>(mut f: F) -> (Duration, usize) {
+    let timer = Instant::now();
+    let mut found = 0;
+    let mut runs = 0;
+    while runs < RUNS && (runs == 0 || timer.elapsed() < MAX_TOTAL) {
+        found = f();
+        runs += 1;
+    }
+    (timer.elapsed() / runs, found)
+}
+
+fn report(search: &str, puzzle: &str, (t, found): (Duration, usize)) {
+    println!(
+        "{:<20} {:<14} {:>12.3} ms {:>6} found",
+        search,
+        puzzle,
+        t.as_secs_f64() * 1000.0,
+        found
+    );
+}
+
+fn main() {
+    let hard = Sudoku::from_line(HARD).unwrap();
+    let puzzles = PUZZLES
+        .iter()
+        .map(|&(name, puzzle)| (name, Sudoku::from_str(puzzle).unwrap()))
+        .chain(std::iter::once(("HARD", hard)));
+    for (name, sudoku) in puzzles {
+        let mut solver_state = sudoku;
+        compute_solve1(&mut solver_state, &Singles);
+        report("find_als", name, time(|| find_als(&solver_state).len()));
+        report(
+            "find_als_xz",
+            name,
+            time(|| find_als_xz(&solver_state).len()),
+        );
+        report(
+            "find_als_xy_wings",
+            name,
+            time(|| find_als_xy_wings(&solver_state).len()),
+        );
+        report(
+            "find_death_blossoms",
+            name,
+            time(|| find_death_blossoms(&solver_state).len()),
+        );
+    }
+}
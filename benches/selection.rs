@@ -0,0 +1,74 @@
+//! Compares the cell selection policies of the backtracking solvers on the example puzzles.
+//!
+//! Run with `cargo bench --bench selection`.
+
+use cde_rust_sudoku::base::*;
+use cde_rust_sudoku::examples::*;
+use cde_rust_sudoku::solve::*;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+const PUZZLES: [(&str, &str); 4] = [
+    ("SUDOKU1", SUDOKU1),
+    ("SUDOKU2", SUDOKU2),
+    ("SUDOKU3", SUDOKU3),
+    ("EMPTY_SUDOKU", EMPTY_SUDOKU),
+];
+
+// every measurement is averaged over up to this many runs, slow ones are only run once
+const RUNS: u32 = 10;
+const MAX_TOTAL: Duration = Duration::from_secs(1);
+
+fn time<F: FnMut() -> bool>(mut f: F) -> (Duration, bool) {
+    let timer = Instant::now();
+    let mut solved = true;
+    let mut runs = 0;
+    while runs < RUNS && (runs == 0 || timer.elapsed() < MAX_TOTAL) {
+        solved &= f();
+        runs += 1;
+    }
+    (timer.elapsed() / runs, solved)
+}
+
+fn report(
+    solver: &str,
+    selection: Option<CellSelection>,
+    puzzle: &str,
+    (t, solved): (Duration, bool),
+) {
+    let selection = selection.map_or("-".to_string(), |s| format!("{:?}", s));
+    println!(
+        "{:<26} {:<20} {:<14} {:>12.3} ms {}",
+        solver,
+        selection,
+        puzzle,
+        t.as_secs_f64() * 1000.0,
+        if solved { "" } else { "(not solved)" }
+    );
+}
+
+fn is_solved(result: SolverResult) -> bool {
+    match result {
+        SolverResult::Solved(_) => true,
+        SolverResult::Contradiction(_) => false,
+    }
+}
+
+fn main() {
+    for &(name, puzzle) in PUZZLES.iter() {
+        let sudoku = Sudoku::from_str(puzzle).unwrap();
+        for &selection in CellSelection::ALL.iter() {
+            let t = time(|| is_solved(brute_force(sudoku, selection)));
+            report("brute_force", Some(selection), name, t);
+            let t = time(|| is_solved(brute_force_with_exclude(sudoku, selection)));
+            report("brute_force_with_exclude", Some(selection), name, t);
+            let t = time(|| {
+                let mut solver_state = sudoku;
+                compute_solve_tree(&mut solver_state, &Singles, selection).0
+            });
+            report("compute_solve_tree", Some(selection), name, t);
+        }
+        let t = time(|| is_solved(dancing_links(sudoku)));
+        report("dancing_links", None, name, t);
+    }
+}
@@ -0,0 +1,467 @@
+//! Almost Locked Sets: ALS-XZ, ALS-XY-Wing and Death Blossom
+//!
+//! An almost locked set (ALS) is a set of n unsolved cells in one house with n+1 candidates
+//! together. If any one of its numbers is removed, the remaining n numbers are locked into it.
+//!
+//! A number x is a restricted common candidate (RCC) of two disjoint sets if every cell of one
+//! set that has x sees every cell of the other that has x, so at most one of them holds x.
+//!
+//! ALS-XZ: if A and B have an RCC x, one of them is locked, so a number z in both is in A or
+//! B, and every cell that sees all z of both can't be z. With two RCCs both sets are locked.
+//! ALS-XY-Wing: A and C share the RCC x, B and C the RCC y; a number z in A and B is removed
+//! like in ALS-XZ.
+//! Death Blossom: every candidate of a stem cell has an ALS (petal) whose cells with that
+//! candidate all see the stem. Whatever the stem is, one petal is locked, so a number z in
+//! every petal can be removed from cells that see all z of all petals.
+//!
+//! Cell sets are bitmasks over the 81 cells in row-major order.
+
+use std::collections::HashSet;
+
+use crate::base::{Elimination, House, Ix, NumberSet, Sudoku};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AlsKind {
+    XZ,
+    DoublyLinkedXZ,
+    XYWing,
+    DeathBlossom,
+}
+
+/// An almost locked set
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Als {
+    pub house: House,
+    pub cells: Vec<(Ix, Ix)>,
+    pub numbers: NumberSet,
+    mask: u128,
+    // per number, the cells of the set that have it
+    number_masks: [u128; 9],
+    // per number, the cells outside of the set that see all its cells with the number
+    seen_by: [u128; 9],
+}
+
+/// A deduction from almost locked sets and the candidates it eliminates
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AlsDeduction {
+    pub kind: AlsKind,
+    /// The sets A and B, for an ALS-XY-Wing followed by the pivot C, for a Death Blossom the
+    /// petals in the order of the stem candidates
+    pub sets: Vec<Als>,
+    /// The stem cell of a Death Blossom
+    pub stem: Option<(Ix, Ix)>,
+    /// The restricted common candidates that link the sets
+    pub restricted: NumberSet,
+    pub eliminations: Vec<Elimination>,
+}
+
+fn index((r, c): (Ix, Ix)) -> usize {
+    usize::from(r) * 9 + usize::from(c)
+}
+
+fn bit(cell: (Ix, Ix)) -> u128 {
+    1 << index(cell)
+}
+
+fn digit(number: NumberSet) -> usize {
+    number.bits().trailing_zeros() as usize
+}
+
+fn cells_of(mut mask: u128) -> impl Iterator<Item = (Ix, Ix)> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let i = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
+        Some((Ix::ALL_INDICES[i / 9], Ix::ALL_INDICES[i % 9]))
+    })
+}
+
+/// Cell masks that are needed by all the searches
+struct Grid {
+    peers: Vec<u128>,
+    // per number, the unsolved cells that have it
+    candidates: [u128; 9],
+}
+
+impl Grid {
+    fn new(solver_state: &Sudoku<NumberSet>) -> Self {
+        let mut peers = vec![0; 81];
+        let mut candidates = [0; 9];
+        for (r, c, cell) in solver_state.iter_with_index() {
+            peers[index((r, c))] = solver_state.peers(r, c).fold(0, |acc, p| acc | bit(p));
+            if cell.len() > 1 {
+                for n in cell.iter() {
+                    candidates[digit(n)] |= bit((r, c));
+                }
+            }
+        }
+        Self { peers, candidates }
+    }
+
+    /// The cells that see all of the given cells, nothing if there are none
+    fn seeing_all(&self, mask: u128) -> u128 {
+        if mask == 0 {
+            return 0;
+        }
+        cells_of(mask).fold(!0, |acc, cell| acc & self.peers[index(cell)])
+    }
+
+    fn eliminations(
+        &self,
+        solver_state: &Sudoku<NumberSet>,
+        targets: u128,
+        number: NumberSet,
+    ) -> Vec<Elimination> {
+        cells_of(targets & self.candidates[digit(number)])
+            .filter_map(|(r, c)| solver_state.elimination(r, c, number))
+            .collect()
+    }
+}
+
+/// Almost locked sets have at most this many cells
+///
+/// Every pair of sets is compared, so larger sets get expensive quickly, and a large set in a
+/// house usually goes with a small locked set in the rest of it, which the subsets find.
+pub const MAX_ALS_SIZE: usize = 4;
+
+fn find_als_in(solver_state: &Sudoku<NumberSet>, grid: &Grid) -> Vec<Als> {
+    let mut found: Vec<Als> = Vec::new();
+    let mut seen = HashSet::new();
+    for house in solver_state.houses() {
+        let unsolved: Vec<(Ix, Ix)> = house
+            .cells()
+            .iter()
+            .cloned()
+            .filter(|&(r, c)| solver_state.get(r, c).len() > 1)
+            .collect();
+        let cands: Vec<NumberSet> = unsolved
+            .iter()
+            .map(|&(r, c)| *solver_state.get(r, c))
+            .collect();
+        for subset in 1u32..(1 << unsolved.len()) {
+            if subset.count_ones() as usize > MAX_ALS_SIZE {
+                continue;
+            }
+            let in_subset = |i: &usize| subset & (1 << i) != 0;
+            let numbers = (0..unsolved.len())
+                .filter(in_subset)
+                .fold(NumberSet::empty(), |acc, i| acc | cands[i]);
+            if numbers.len() != subset.count_ones() as usize + 1 {
+                continue;
+            }
+            let cells: Vec<(Ix, Ix)> = (0..unsolved.len())
+                .filter(in_subset)
+                .map(|i| unsolved[i])
+                .collect();
+            let mask = cells.iter().fold(0, |acc, &cell| acc | bit(cell));
+            // sets in the intersection of a line and a block are found twice
+            if !seen.insert(mask) {
+                continue;
+            }
+            let mut number_masks = [0; 9];
+            let mut seen_by = [0; 9];
+            for n in numbers.iter() {
+                number_masks[digit(n)] = mask & grid.candidates[digit(n)];
+                seen_by[digit(n)] = grid.seeing_all(number_masks[digit(n)]) & !mask;
+            }
+            found.push(Als {
+                house,
+                cells,
+                numbers,
+                mask,
+                number_masks,
+                seen_by,
+            });
+        }
+    }
+    found
+}
+
+/// Finds all almost locked sets of up to `MAX_ALS_SIZE` cells, including single bivalue cells
+pub fn find_als(solver_state: &Sudoku<NumberSet>) -> Vec<Als> {
+    find_als_in(solver_state, &Grid::new(solver_state))
+}
+
+/// The restricted common candidates of two sets
+fn restricted_common(a: &Als, b: &Als) -> NumberSet {
+    if a.mask & b.mask != 0 {
+        return NumberSet::empty();
+    }
+    (a.numbers & b.numbers)
+        .iter()
+        .filter(|&x| b.number_masks[digit(x)] & !a.seen_by[digit(x)] == 0)
+        .fold(NumberSet::empty(), |acc, x| acc | x)
+}
+
+/// Eliminations of z from cells that see all z of both sets
+fn common_eliminations(
+    solver_state: &Sudoku<NumberSet>,
+    grid: &Grid,
+    a: &Als,
+    b: &Als,
+    z: NumberSet,
+) -> Vec<Elimination> {
+    let targets = a.seen_by[digit(z)] & b.seen_by[digit(z)];
+    grid.eliminations(solver_state, targets, z)
+}
+
+/// Finds ALS-XZ eliminations, singly and doubly linked
+pub fn find_als_xz(solver_state: &Sudoku<NumberSet>) -> Vec<AlsDeduction> {
+    let grid = Grid::new(solver_state);
+    let sets = find_als_in(solver_state, &grid);
+    let mut found = Vec::new();
+    for (i, a) in sets.iter().enumerate() {
+        for b in sets[i + 1..].iter() {
+            let restricted = restricted_common(a, b);
+            let mut eliminations: Vec<Elimination> = Vec::new();
+            let kind = match restricted.len() {
+                0 => continue,
+                1 => {
+                    for z in ((a.numbers & b.numbers) - restricted).iter() {
+                        eliminations.extend(common_eliminations(solver_state, &grid, a, b, z));
+                    }
+                    AlsKind::XZ
+                }
+                _ => {
+                    // both sets are locked: every number of one set can be removed from cells
+                    // that see all its cells with that number
+                    for x in restricted.iter() {
+                        eliminations.extend(common_eliminations(solver_state, &grid, a, b, x));
+                    }
+                    for set in [a, b].iter() {
+                        for z in (set.numbers - restricted).iter() {
+                            eliminations.extend(grid.eliminations(
+                                solver_state,
+                                set.seen_by[digit(z)] & !a.mask & !b.mask,
+                                z,
+                            ));
+                        }
+                    }
+                    AlsKind::DoublyLinkedXZ
+                }
+            };
+            eliminations.sort_by_key(|e| (e.row, e.col, e.numbers));
+            eliminations.dedup();
+            if !eliminations.is_empty() {
+                found.push(AlsDeduction {
+                    kind,
+                    sets: vec![a.clone(), b.clone()],
+                    stem: None,
+                    restricted,
+                    eliminations,
+                });
+            }
+        }
+    }
+    found
+}
+
+/// Finds ALS-XY-Wings
+pub fn find_als_xy_wings(solver_state: &Sudoku<NumberSet>) -> Vec<AlsDeduction> {
+    let grid = Grid::new(solver_state);
+    let sets = find_als_in(solver_state, &grid);
+    let mut found = Vec::new();
+    // the sets that have an RCC with each set
+    let linked: Vec<Vec<(usize, NumberSet)>> = sets
+        .iter()
+        .map(|c| {
+            sets.iter()
+                .enumerate()
+                .map(|(j, other)| (j, restricted_common(c, other)))
+                .filter(|(_, restricted)| !restricted.is_empty())
+                .collect()
+        })
+        .collect();
+    for (ci, c) in sets.iter().enumerate() {
+        for (k, &(ai, xs)) in linked[ci].iter().enumerate() {
+            for &(bi, ys) in linked[ci][k + 1..].iter() {
+                let (a, b) = (&sets[ai], &sets[bi]);
+                for x in xs.iter() {
+                    for y in ys.iter().filter(|&y| y != x) {
+                        let zs = (a.numbers & b.numbers) - x - y;
+                        let eliminations: Vec<Elimination> = zs
+                            .iter()
+                            .flat_map(|z| common_eliminations(solver_state, &grid, a, b, z))
+                            .collect();
+                        if !eliminations.is_empty() {
+                            found.push(AlsDeduction {
+                                kind: AlsKind::XYWing,
+                                sets: vec![a.clone(), b.clone(), c.clone()],
+                                stem: None,
+                                restricted: x | y,
+                                eliminations,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    found
+}
+
+/// Stem cells of Death Blossoms have at most this many candidates
+pub const MAX_STEM_SIZE: usize = 3;
+
+/// Finds Death Blossoms
+pub fn find_death_blossoms(solver_state: &Sudoku<NumberSet>) -> Vec<AlsDeduction> {
+    let grid = Grid::new(solver_state);
+    let sets = find_als_in(solver_state, &grid);
+    let mut found = Vec::new();
+    for (r, c, &stem_numbers) in solver_state.iter_with_index() {
+        if stem_numbers.len() < 2 || stem_numbers.len() > MAX_STEM_SIZE {
+            continue;
+        }
+        let stem = (r, c);
+        // for each stem candidate, the sets whose cells with it all see the stem
+        let petals: Vec<Vec<&Als>> = stem_numbers
+            .iter()
+            .map(|n| {
+                sets.iter()
+                    .filter(|set| set.mask & bit(stem) == 0 && set.numbers.contains(n))
+                    .filter(|set| set.number_masks[digit(n)] & !grid.peers[index(stem)] == 0)
+                    .collect()
+            })
+            .collect();
+        for z in (NumberSet::all() - stem_numbers).iter() {
+            let mut chosen = Vec::new();
+            blossom(
+                solver_state,
+                &grid,
+                &petals,
+                z,
+                grid.candidates[digit(z)],
+                &mut chosen,
+                &mut |chosen, eliminations| {
+                    found.push(AlsDeduction {
+                        kind: AlsKind::DeathBlossom,
+                        sets: chosen.iter().map(|&set| set.clone()).collect(),
+                        stem: Some(stem),
+                        restricted: stem_numbers,
+                        eliminations,
+                    })
+                },
+            );
+        }
+    }
+    found
+}
+
+// chooses one petal per stem candidate, narrowing down the cells that could lose z
+fn blossom<'a>(
+    solver_state: &Sudoku<NumberSet>,
+    grid: &Grid,
+    petals: &[Vec<&'a Als>],
+    z: NumberSet,
+    targets: u128,
+    chosen: &mut Vec<&'a Als>,
+    on_found: &mut dyn FnMut(&[&'a Als], Vec<Elimination>),
+) {
+    if targets == 0 {
+        return;
+    }
+    if chosen.len() == petals.len() {
+        on_found(chosen, grid.eliminations(solver_state, targets, z));
+        return;
+    }
+    for &petal in petals[chosen.len()].iter() {
+        if !petal.numbers.contains(z) {
+            continue;
+        }
+        chosen.push(petal);
+        let narrowed = targets & petal.seen_by[digit(z)];
+        blossom(solver_state, grid, petals, z, narrowed, chosen, on_found);
+        chosen.pop();
+    }
+}
+
+fn apply(solver_state: &mut Sudoku<NumberSet>, found: Vec<AlsDeduction>) -> Vec<AlsDeduction> {
+    solver_state.apply_eliminations(found.iter().flat_map(|d| &d.eliminations));
+    found
+}
+
+/// compute field constraints: remove candidates using ALS-XZ
+pub fn compute_als_xz(solver_state: &mut Sudoku<NumberSet>) -> Vec<AlsDeduction> {
+    let found = find_als_xz(solver_state);
+    apply(solver_state, found)
+}
+
+/// compute field constraints: remove candidates using ALS-XY-Wings
+pub fn compute_als_xy_wings(solver_state: &mut Sudoku<NumberSet>) -> Vec<AlsDeduction> {
+    let found = find_als_xy_wings(solver_state);
+    apply(solver_state, found)
+}
+
+/// compute field constraints: remove candidates using Death Blossoms
+pub fn compute_death_blossoms(solver_state: &mut Sudoku<NumberSet>) -> Vec<AlsDeduction> {
+    let found = find_death_blossoms(solver_state);
+    apply(solver_state, found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{Ix1, Ix5};
+    use crate::examples::*;
+    use std::str::FromStr;
+
+    const N1: NumberSet = NumberSet::N1;
+    const N2: NumberSet = NumberSet::N2;
+    const N3: NumberSet = NumberSet::N3;
+
+    fn eliminates(deduction: &AlsDeduction, cell: (Ix, Ix), number: NumberSet) -> bool {
+        deduction
+            .eliminations
+            .iter()
+            .any(|e| (e.row, e.col) == cell && e.numbers == number)
+    }
+
+    #[test]
+    fn test_als_xz() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        *sudoku.get_mut(Ix1, Ix1) = N1 | N2;
+        *sudoku.get_mut(Ix5, Ix1) = N1 | N3;
+        *sudoku.get_mut(Ix5, Ix5) = N2 | N3;
+        let sets = find_als(&sudoku);
+        assert!(sets.iter().any(|set| set.cells == vec![(Ix1, Ix1)]));
+        // A = r1c1 and B = r5c1 + r5c5 are linked by 1 in column 1, so one of them has the 2
+        let found = find_als_xz(&sudoku);
+        let xz = found
+            .iter()
+            .find(|d| {
+                d.sets[0].cells == vec![(Ix1, Ix1)]
+                    && d.sets[1].cells == vec![(Ix5, Ix1), (Ix5, Ix5)]
+            })
+            .unwrap();
+        assert_eq!(xz.kind, AlsKind::XZ);
+        assert_eq!(xz.restricted, N1);
+        assert_eq!(xz.eliminations.len(), 1);
+        assert!(eliminates(xz, (Ix1, Ix5), N2));
+    }
+
+    #[test]
+    fn test_als_xy_wing_and_death_blossom() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        *sudoku.get_mut(Ix5, Ix5) = N1 | N2;
+        *sudoku.get_mut(Ix5, Ix1) = N1 | N3;
+        *sudoku.get_mut(Ix1, Ix5) = N2 | N3;
+        let wing = find_als_xy_wings(&sudoku)
+            .into_iter()
+            .find(|d| d.sets[2].cells == vec![(Ix5, Ix5)] && d.sets[0].cells.len() == 1)
+            .unwrap();
+        assert_eq!(wing.restricted, N1 | N2);
+        assert!(eliminates(&wing, (Ix1, Ix1), N3));
+
+        let blossom = find_death_blossoms(&sudoku)
+            .into_iter()
+            .find(|d| d.stem == Some((Ix5, Ix5)) && d.sets.iter().all(|s| s.cells.len() == 1))
+            .unwrap();
+        assert_eq!(blossom.sets[0].cells, vec![(Ix5, Ix1)]);
+        assert_eq!(blossom.sets[1].cells, vec![(Ix1, Ix5)]);
+        assert!(eliminates(&blossom, (Ix1, Ix1), N3));
+        compute_death_blossoms(&mut sudoku);
+        assert!(!sudoku.get(Ix1, Ix1).contains(N3));
+    }
+}
@@ -1,6 +1,9 @@
+use lazy_static::lazy_static;
 use std::convert::TryFrom;
 use std::fmt::{Display, Formatter};
+use std::ops::{BitAnd, BitOr, Sub};
 use std::str::FromStr;
+use std::sync::Mutex;
 
 const SUDOKUSIZE: usize = 9;
 
@@ -40,6 +43,17 @@ impl NumberSet {
     pub fn is_singleton(&self) -> bool {
         self.bits().count_ones() == 1
     }
+    /// Number of flags set
+    pub fn len(&self) -> usize {
+        self.bits().count_ones() as usize
+    }
+    /// The numbers in this set, each as a singleton set
+    pub fn iter(self) -> impl Iterator<Item = NumberSet> {
+        NumberSet::VALUES
+            .iter()
+            .cloned()
+            .filter(move |n| self.contains(*n))
+    }
 }
 
 impl TryFrom<char> for NumberSet {
@@ -65,16 +79,16 @@ impl TryFrom<char> for NumberSet {
 
 impl Display for NumberSet {
     fn fmt(&self, mut formatter: &mut Formatter) -> Result<(), std::fmt::Error> {
-        let n: char = match self {
-            &NumberSet::N1 => '1',
-            &NumberSet::N2 => '2',
-            &NumberSet::N3 => '3',
-            &NumberSet::N4 => '4',
-            &NumberSet::N5 => '5',
-            &NumberSet::N6 => '6',
-            &NumberSet::N7 => '7',
-            &NumberSet::N8 => '8',
-            &NumberSet::N9 => '9',
+        let n: char = match *self {
+            NumberSet::N1 => '1',
+            NumberSet::N2 => '2',
+            NumberSet::N3 => '3',
+            NumberSet::N4 => '4',
+            NumberSet::N5 => '5',
+            NumberSet::N6 => '6',
+            NumberSet::N7 => '7',
+            NumberSet::N8 => '8',
+            NumberSet::N9 => '9',
             _ => ' ',
         };
         write!(&mut formatter, "{}", n)
@@ -83,7 +97,7 @@ impl Display for NumberSet {
 
 /// Indexing type for rows and columns for compile-time bounds checks
 #[repr(usize)]
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Ix {
     Ix1,
     Ix2,
@@ -102,6 +116,21 @@ impl Ix {
     pub fn all_indices() -> impl Iterator<Item = Ix> {
         Self::ALL_INDICES.iter().cloned()
     }
+
+    /// The next index, or None after Ix9
+    pub fn succ(self) -> Option<Ix> {
+        match self {
+            Ix1 => Some(Ix2),
+            Ix2 => Some(Ix3),
+            Ix3 => Some(Ix4),
+            Ix4 => Some(Ix5),
+            Ix5 => Some(Ix6),
+            Ix6 => Some(Ix7),
+            Ix7 => Some(Ix8),
+            Ix8 => Some(Ix9),
+            Ix9 => None,
+        }
+    }
 }
 
 impl From<Ix> for usize {
@@ -136,126 +165,333 @@ impl From<Ix> for NumberSet {
     }
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone)]
 pub struct Sudoku<T> {
     // row-major
     arr: [[T; 9]; 9],
+    // interned, so that the sudoku stays Copy
+    layout: &'static Layout,
+}
+
+/// Layouts are interned, so equal layouts are the same reference
+impl<T: PartialEq> PartialEq for Sudoku<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.arr == other.arr && std::ptr::eq(self.layout, other.layout)
+    }
 }
 
-pub fn conv_sudoku_type<T: Copy, U: From<T>>(input_T: Sudoku<T>, output_U: &mut Sudoku<U>) {
+pub fn conv_sudoku_type<T: Copy, U: From<T>>(input: Sudoku<T>, output: &mut Sudoku<U>) {
     for i in Ix::all_indices() {
         for j in Ix::all_indices() {
-            *output_U.get_mut(i, j) = U::from(*input_T.get(i, j))
+            *output.get_mut(i, j) = U::from(*input.get(i, j))
         }
     }
+    output.layout = input.layout;
 }
 
 impl<T> Sudoku<T> {
-    pub fn get<'a>(&'a self, r: Ix, c: Ix) -> &'a T {
+    /// The houses: rows, columns, blocks (3x3 unless the layout was replaced) and extra houses
+    pub fn layout(&self) -> &'static Layout {
+        self.layout
+    }
+
+    /// Replaces the 3x3 blocks by the regions of another layout, e.g. of a jigsaw sudoku.
+    /// Returns an error if the layout is not 9x9 or has more than nine houses of a kind.
+    pub fn with_layout(mut self, layout: Layout) -> Result<Self, String> {
+        if layout.size() != SUDOKUSIZE {
+            return Err(format!("Expected a 9x9 layout, got {0}x{0}", layout.size()));
+        }
+        let kinds = &layout.kinds;
+        if let Some(kind) = kinds
+            .iter()
+            .find(|&&kind| kinds.iter().filter(|&&k| k == kind).count() > SUDOKUSIZE)
+        {
+            return Err(format!("Expected at most 9 houses of kind {:?}", kind));
+        }
+        self.layout = layout.intern();
+        Ok(self)
+    }
+
+    pub fn get(&self, r: Ix, c: Ix) -> &T {
         &self.arr[usize::from(r)][usize::from(c)]
     }
 
-    pub fn get_mut<'a>(&'a mut self, r: Ix, c: Ix) -> &'a mut T {
+    pub fn get_mut(&mut self, r: Ix, c: Ix) -> &mut T {
         &mut self.arr[usize::from(r)][usize::from(c)]
     }
 
-    pub fn iter<'a>(&'a self) -> impl Iterator<Item = &'a T> {
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
         self.arr.iter().flat_map(|row| row.iter())
     }
 
-    pub fn iter_mut<'a>(&'a mut self) -> impl Iterator<Item = &'a mut T> {
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
         self.arr.iter_mut().flat_map(|row| row.iter_mut())
     }
 
-    pub fn iter_with_index<'a>(&'a self) -> impl Iterator<Item = (Ix, Ix, &'a T)> {
+    pub fn iter_with_index(&self) -> impl Iterator<Item = (Ix, Ix, &T)> {
         let indices = Ix::all_indices().flat_map(|r| Ix::all_indices().map(move |c| (r, c)));
         indices.map(move |(r, c)| (r, c, &self.arr[usize::from(r)][usize::from(c)]))
     }
 
-    pub fn row<'a>(&'a self, r: Ix) -> impl Iterator<Item = &'a T> {
+    pub fn row(&self, r: Ix) -> impl Iterator<Item = &T> {
         self.arr[usize::from(r)].iter()
     }
 
-    pub fn row_mut<'a>(&'a mut self, r: Ix) -> impl Iterator<Item = &'a mut T> {
+    pub fn row_mut(&mut self, r: Ix) -> impl Iterator<Item = &mut T> {
         self.arr[usize::from(r)].iter_mut()
     }
 
-    pub fn col<'a>(&'a self, c: Ix) -> impl Iterator<Item = &'a T> {
+    pub fn col(&self, c: Ix) -> impl Iterator<Item = &T> {
         self.arr.iter().map(move |row| &row[usize::from(c)])
     }
 
-    pub fn col_mut<'a>(&'a mut self, c: Ix) -> impl Iterator<Item = &'a mut T> {
+    pub fn col_mut(&mut self, c: Ix) -> impl Iterator<Item = &mut T> {
         self.arr.iter_mut().map(move |row| &mut row[usize::from(c)])
     }
 
-    pub fn block<'a>(&'a self, block_ix: Ix) -> impl Iterator<Item = &'a T> {
-        let r_min = (usize::from(block_ix) / 3) * 3;
-        let c_min = (usize::from(block_ix) % 3) * 3;
-        self.arr[r_min..r_min + 3]
+    pub fn block(&self, block_ix: Ix) -> impl Iterator<Item = &T> {
+        self.layout
+            .region(usize::from(block_ix))
             .iter()
-            .flat_map(move |row| row[c_min..c_min + 3].iter())
+            .map(move |&(r, c)| &self.arr[r][c])
     }
 
-    pub fn block_mut<'a>(&'a mut self, block_ix: Ix) -> impl Iterator<Item = &'a mut T> {
-        let r_min = (usize::from(block_ix) / 3) * 3;
-        let c_min = (usize::from(block_ix) % 3) * 3;
-        self.arr[r_min..r_min + 3]
-            .iter_mut()
-            .flat_map(move |row| row[c_min..c_min + 3].iter_mut())
+    pub fn block_mut(&mut self, block_ix: Ix) -> impl Iterator<Item = &mut T> {
+        let layout = self.layout;
+        self.arr.iter_mut().enumerate().flat_map(move |(r, row)| {
+            row.iter_mut()
+                .enumerate()
+                .filter(move |&(c, _)| layout.region_of(r, c) == usize::from(block_ix))
+                .map(|(_, cell)| cell)
+        })
     }
 
-    pub fn block_for_cell<'a>(&'a self, r: Ix, c: Ix) -> impl Iterator<Item = &'a T> {
-        let r_min = (usize::from(r) / 3) * 3;
-        let c_min = (usize::from(c) / 3) * 3;
-        self.arr[r_min..r_min + 3]
-            .iter()
-            .flat_map(move |row| row[c_min..c_min + 3].iter())
+    pub fn block_for_cell(&self, r: Ix, c: Ix) -> impl Iterator<Item = &T> {
+        let block_ix = self.layout.region_of(usize::from(r), usize::from(c));
+        self.block(Ix::ALL_INDICES[block_ix])
+    }
+
+    pub fn block_for_cell_mut(&mut self, r: Ix, c: Ix) -> impl Iterator<Item = &mut T> {
+        let block_ix = self.layout.region_of(usize::from(r), usize::from(c));
+        self.block_mut(Ix::ALL_INDICES[block_ix])
+    }
+
+    pub fn regions(&self) -> Regions<'_, T> {
+        Regions::new(self)
     }
 
-    pub fn block_for_cell_mut<'a>(&'a mut self, r: Ix, c: Ix) -> impl Iterator<Item = &'a mut T> {
-        let r_min = (usize::from(r) as usize / 3) * 3;
-        let c_min = (usize::from(c) as usize / 3) * 3;
-        self.arr[r_min..r_min + 3]
+    /// All houses of the layout: first the rows, then the columns, then the blocks, then the
+    /// extra houses of a variant
+    pub fn houses(&self) -> Houses {
+        Houses::for_layout(self.layout)
+    }
+
+    /// The cells of a house in row-major order
+    pub fn house(&self, house: House) -> impl Iterator<Item = &T> {
+        (0..SUDOKUSIZE).map(move |k| {
+            let (r, c) = house.cells[k];
+            &self.arr[usize::from(r)][usize::from(c)]
+        })
+    }
+
+    /// The cells of a house in row-major order
+    pub fn house_mut(&mut self, house: House) -> impl Iterator<Item = &mut T> {
+        self.arr
             .iter_mut()
-            .flat_map(move |row| row[c_min..c_min + 3].iter_mut())
+            .zip(Ix::all_indices())
+            .flat_map(move |(row, r)| {
+                row.iter_mut()
+                    .zip(Ix::all_indices())
+                    .filter(move |&(_, c)| house.contains(r, c))
+                    .map(|(cell, _)| cell)
+            })
     }
 
-    pub fn regions<'a>(&'a self) -> Regions<'a, T> {
-        unimplemented!()
+    /// The houses that contain a cell
+    pub fn houses_for_cell(&self, r: Ix, c: Ix) -> impl Iterator<Item = House> {
+        self.houses().filter(move |house| house.contains(r, c))
+    }
+
+    /// Checks if two different cells share a house
+    pub fn sees(&self, (r1, c1): (Ix, Ix), (r2, c2): (Ix, Ix)) -> bool {
+        self.layout
+            .peers(usize::from(r1), usize::from(c1))
+            .binary_search(&(usize::from(r2), usize::from(c2)))
+            .is_ok()
+    }
+
+    /// The cells that share a house with the given cell, in row-major order
+    pub fn peers(&self, r: Ix, c: Ix) -> impl Iterator<Item = (Ix, Ix)> {
+        self.layout
+            .peers(usize::from(r), usize::from(c))
+            .iter()
+            .map(|&(r, c)| (Ix::ALL_INDICES[r], Ix::ALL_INDICES[c]))
     }
 }
 
-#[derive(Copy, Clone)]
-enum RegionType {
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RegionType {
     Row,
     Col,
     Block,
-    Ended,
+    /// A main diagonal of Sudoku-X
+    Diagonal,
+    /// A window of Windoku, between the blocks
+    Window,
+    /// The center cells of the blocks
+    CenterDot,
+    /// The cells at the same position in every block
+    DisjointGroup,
 }
 
-impl RegionType {
-    fn succ(self) -> Self {
-        match self {
-            Self::Row => Self::Col,
-            Self::Col => Self::Block,
-            Self::Block => Self::Ended,
-            Self::Ended => Self::Ended,
+/// A row, column, block or extra house of a variant: nine cells which contain every number
+/// exactly once
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct House {
+    ty: RegionType,
+    ix: Ix,
+    // row-major
+    cells: [(Ix, Ix); SUDOKUSIZE],
+}
+
+impl House {
+    /// A row, a column or a block of the standard 3x3 layout. The extra houses of variants
+    /// only exist in the layout of a sudoku, see `Sudoku::houses`.
+    pub fn new(ty: RegionType, ix: Ix) -> Self {
+        let kind = match ty {
+            RegionType::Row => 0,
+            RegionType::Col => 1,
+            RegionType::Block => 2,
+            _ => panic!("the standard layout has no {:?}", ty),
+        };
+        Self::from_layout(*SUDOKU_LAYOUT, kind * SUDOKUSIZE + usize::from(ix))
+    }
+
+    /// The house of a 9x9 layout at the given index in `Layout::houses`, numbered among the
+    /// houses of its kind
+    fn from_layout(layout: &Layout, index: usize) -> Self {
+        let ty = layout.kinds[index];
+        let ix = layout.kinds[..index].iter().filter(|&&kind| kind == ty).count();
+        let mut cells = [(Ix1, Ix1); SUDOKUSIZE];
+        for (cell, &(r, c)) in cells.iter_mut().zip(layout.houses()[index].iter()) {
+            *cell = (Ix::ALL_INDICES[r], Ix::ALL_INDICES[c]);
+        }
+        Self {
+            ty,
+            ix: Ix::ALL_INDICES[ix],
+            cells,
         }
     }
+
+    pub fn row(r: Ix) -> Self {
+        Self::new(RegionType::Row, r)
+    }
+
+    pub fn col(c: Ix) -> Self {
+        Self::new(RegionType::Col, c)
+    }
+
+    /// A block of the standard 3x3 layout
+    pub fn block(block_ix: Ix) -> Self {
+        Self::new(RegionType::Block, block_ix)
+    }
+
+    /// The row, column and block of the standard 3x3 layout that contain a cell, see
+    /// `Sudoku::houses_for_cell` for other layouts
+    pub fn for_cell(r: Ix, c: Ix) -> [House; 3] {
+        let block_ix = SUDOKU_LAYOUT.region_of(usize::from(r), usize::from(c));
+        [
+            Self::row(r),
+            Self::col(c),
+            Self::block(Ix::ALL_INDICES[block_ix]),
+        ]
+    }
+
+    pub fn kind(&self) -> RegionType {
+        self.ty
+    }
+
+    pub fn index(&self) -> Ix {
+        self.ix
+    }
+
+    /// Coordinates (row, column) of the cells in row-major order
+    pub fn cells(&self) -> &[(Ix, Ix)] {
+        &self.cells
+    }
+
+    pub fn contains(&self, r: Ix, c: Ix) -> bool {
+        match self.ty {
+            RegionType::Row => r == self.ix,
+            RegionType::Col => c == self.ix,
+            _ => self.cells.contains(&(r, c)),
+        }
+    }
+}
+
+/// e.g. "row 4", "column 2", "block 5" or "diagonal 1"
+impl Display for House {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let kind = match self.ty {
+            RegionType::Row => "row",
+            RegionType::Col => "column",
+            RegionType::Block => "block",
+            RegionType::Diagonal => "diagonal",
+            RegionType::Window => "window",
+            RegionType::CenterDot => "center dot",
+            RegionType::DisjointGroup => "disjoint group",
+        };
+        write!(formatter, "{} {}", kind, usize::from(self.ix) + 1)
+    }
 }
 
+/// Iterator over all houses of a sudoku, see `Sudoku::houses`
+pub struct Houses {
+    layout: &'static Layout,
+    index: usize,
+}
+
+impl Houses {
+    /// The houses of the standard 3x3 layout
+    pub fn new() -> Self {
+        Self::for_layout(*SUDOKU_LAYOUT)
+    }
+
+    pub fn for_layout(layout: &'static Layout) -> Self {
+        Self { layout, index: 0 }
+    }
+}
+
+impl Default for Houses {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Iterator for Houses {
+    type Item = House;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index == self.layout.houses().len() {
+            return None;
+        }
+        let res = House::from_layout(self.layout, self.index);
+        self.index += 1;
+        Some(res)
+    }
+}
+
+/// Iterator over the contents of all houses of a sudoku, see `Sudoku::regions`
 pub struct Regions<'a, T> {
     sudoku: &'a Sudoku<T>,
-    ix: Ix,
-    ty: RegionType,
+    houses: Houses,
 }
 
 impl<'a, T> Regions<'a, T> {
     pub fn new(sudoku: &'a Sudoku<T>) -> Self {
         Self {
             sudoku,
-            ix: Ix1,
-            ty: RegionType::Row,
+            houses: sudoku.houses(),
         }
     }
 }
@@ -263,226 +499,596 @@ impl<'a, T> Regions<'a, T> {
 impl<'a, T> Iterator for Regions<'a, T> {
     type Item = Box<dyn Iterator<Item = &'a T> + 'a>;
 
-    fn next(&'_ mut self) -> Option<Self::Item> {
-        let res = match self.ty {
-            RegionType::Row => {
-                Box::new(self.sudoku.row(self.ix)) as Box<dyn Iterator<Item = &'a T>>
+    fn next(&mut self) -> Option<Self::Item> {
+        let house = self.houses.next()?;
+        Some(Box::new(self.sudoku.house(house)))
+    }
+}
+
+/// The houses of a grid: rows, columns and regions, each must contain every number once
+///
+/// The regions are the boxes of the grid, or the irregular shapes of a jigsaw sudoku. Variants
+/// add extra houses on top, e.g. the diagonals of Sudoku-X.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Layout {
+    size: usize,
+    // width and height of the boxes, None for jigsaw regions
+    box_shape: Option<(usize, usize)>,
+    houses: Vec<Vec<(usize, usize)>>,
+    // the kind of each house
+    kinds: Vec<RegionType>,
+    // for each cell in row-major order, the cells that share a house with it
+    peers: Vec<Vec<(usize, usize)>>,
+    // for each cell in row-major order, the index of its region
+    regions: Vec<usize>,
+}
+
+impl Layout {
+    fn from_houses(
+        size: usize,
+        box_shape: Option<(usize, usize)>,
+        houses: Vec<Vec<(usize, usize)>>,
+        kinds: Vec<RegionType>,
+    ) -> Self {
+        let mut peers = vec![Vec::new(); size * size];
+        for house in houses.iter() {
+            for &(r, c) in house.iter() {
+                peers[r * size + c].extend(house.iter().filter(|&&cell| cell != (r, c)));
             }
-            RegionType::Col => {
-                Box::new(self.sudoku.col(self.ix)) as Box<dyn Iterator<Item = &'a T>>
+        }
+        for cell_peers in peers.iter_mut() {
+            cell_peers.sort_unstable();
+            cell_peers.dedup();
+        }
+        // the regions follow the rows and columns
+        let mut regions = vec![0; size * size];
+        for (region, house) in houses[2 * size..3 * size].iter().enumerate() {
+            for &(r, c) in house.iter() {
+                regions[r * size + c] = region;
             }
-            RegionType::Block => {
-                Box::new(self.sudoku.block(self.ix)) as Box<dyn Iterator<Item = &'a T>>
+        }
+        Layout {
+            size,
+            box_shape,
+            houses,
+            kinds,
+            peers,
+            regions,
+        }
+    }
+
+    fn with_regions(
+        size: usize,
+        box_shape: Option<(usize, usize)>,
+        regions: impl Iterator<Item = Vec<(usize, usize)>>,
+    ) -> Self {
+        let rows = (0..size).map(|r| (0..size).map(|c| (r, c)).collect());
+        let cols = (0..size).map(|c| (0..size).map(|r| (r, c)).collect());
+        let houses: Vec<Vec<(usize, usize)>> = rows.chain(cols).chain(regions).collect();
+        let kinds = [RegionType::Row, RegionType::Col, RegionType::Block]
+            .iter()
+            .flat_map(|&kind| vec![kind; size])
+            .collect();
+        Self::from_houses(size, box_shape, houses, kinds)
+    }
+
+    /// Boxes `box_width` cells wide and `box_height` cells high, numbered row-major
+    pub fn boxes(box_width: usize, box_height: usize) -> Self {
+        let size = box_width * box_height;
+        let boxes = (0..size).map(|b| {
+            let r0 = b / box_height * box_height;
+            let c0 = b % box_height * box_width;
+            (0..size)
+                .map(|k| (r0 + k / box_width, c0 + k % box_width))
+                .collect()
+        });
+        Self::with_regions(size, Some((box_width, box_height)), boxes)
+    }
+
+    /// Parses a region map for a jigsaw sudoku: one character per cell in row-major order,
+    /// cells with the same character form a region. Whitespace is ignored, so the map can be
+    /// written as one line per row. Every region must be a connected shape of `size` cells.
+    pub fn jigsaw(size: usize, map: &str) -> Result<Self, String> {
+        let chars: Vec<char> = map.chars().filter(|c| !c.is_whitespace()).collect();
+        if chars.len() != size * size {
+            return Err(format!(
+                "Expected {} cells in the region map, got {}",
+                size * size,
+                chars.len()
+            ));
+        }
+        let mut names: Vec<char> = chars.clone();
+        names.sort_unstable();
+        names.dedup();
+        if names.len() != size {
+            return Err(format!("Expected {} regions, got {}", size, names.len()));
+        }
+        let mut regions = Vec::new();
+        for &name in names.iter() {
+            let region: Vec<(usize, usize)> = (0..size * size)
+                .filter(|&i| chars[i] == name)
+                .map(|i| (i / size, i % size))
+                .collect();
+            if region.len() != size {
+                return Err(format!(
+                    "Region {} has {} cells instead of {}",
+                    name,
+                    region.len(),
+                    size
+                ));
             }
-            RegionType::Ended => {
-                return None;
+            if !is_connected(&region) {
+                return Err(format!("Region {} is not connected", name));
             }
-        };
+            regions.push(region);
+        }
+        Ok(Self::with_regions(size, None, regions.into_iter()))
+    }
 
-        if Ix9 == self.ix {
-            self.ty = self.ty.succ();
-        };
+    /// Adds extra houses of a kind. Returns an error unless each house has `size` different
+    /// cells of the grid, or if the kind is one of the rows, columns and regions.
+    pub fn with_houses(
+        self,
+        kind: RegionType,
+        extra: impl IntoIterator<Item = Vec<(usize, usize)>>,
+    ) -> Result<Self, String> {
+        if let RegionType::Row | RegionType::Col | RegionType::Block = kind {
+            return Err(format!("{:?} can't be an extra house", kind));
+        }
+        let size = self.size;
+        let mut houses = self.houses;
+        let mut kinds = self.kinds;
+        for house in extra {
+            let mut cells = house.clone();
+            cells.sort_unstable();
+            cells.dedup();
+            if cells.len() != size || cells.iter().any(|&(r, c)| r >= size || c >= size) {
+                return Err(format!(
+                    "Expected {} different cells of a {}x{} grid, got {:?}",
+                    size, size, size, house
+                ));
+            }
+            houses.push(cells);
+            kinds.push(kind);
+        }
+        Ok(Self::from_houses(size, self.box_shape, houses, kinds))
+    }
 
-        self.ix = match self.ix {
-            Ix1 => Ix2,
-            Ix2 => Ix3,
-            Ix3 => Ix4,
-            Ix4 => Ix5,
-            Ix5 => Ix6,
-            Ix6 => Ix7,
-            Ix7 => Ix8,
-            Ix8 => Ix9,
-            Ix9 => Ix1,
+    /// Adds both main diagonals (Sudoku-X)
+    pub fn with_diagonals(self) -> Result<Self, String> {
+        let n = self.size;
+        let diagonal = (0..n).map(|i| (i, i)).collect();
+        let anti_diagonal = (0..n).map(|i| (i, n - 1 - i)).collect();
+        self.with_houses(RegionType::Diagonal, vec![diagonal, anti_diagonal])
+    }
+
+    /// Adds the windows of Windoku or Hyper Sudoku: boxes shifted by one cell, with one cell
+    /// between them. A 9x9 grid gets four windows. Returns an error for jigsaw regions.
+    pub fn with_windows(self) -> Result<Self, String> {
+        let (box_width, box_height) = self.box_shape.ok_or("Windows need a layout with boxes")?;
+        let n = self.size;
+        let starts = |length: usize| {
+            (0..)
+                .map(move |k| 1 + k * (length + 1))
+                .take_while(move |&start| start + length <= n)
         };
+        let windows: Vec<Vec<(usize, usize)>> = starts(box_height)
+            .flat_map(|r0| starts(box_width).map(move |c0| (r0, c0)))
+            .map(|(r0, c0)| {
+                (0..n)
+                    .map(|k| (r0 + k / box_width, c0 + k % box_width))
+                    .collect()
+            })
+            .collect();
+        self.with_houses(RegionType::Window, windows)
+    }
 
-        Some(res)
+    /// The cells at the same position in every box
+    fn disjoint_group(
+        &self,
+        (box_width, box_height): (usize, usize),
+        dr: usize,
+        dc: usize,
+    ) -> Vec<(usize, usize)> {
+        (0..self.size)
+            .map(|b| {
+                let r0 = b / box_height * box_height;
+                let c0 = b % box_height * box_width;
+                (r0 + dr, c0 + dc)
+            })
+            .collect()
+    }
+
+    /// Adds the center cells of the boxes. Returns an error for jigsaw regions and for boxes
+    /// with an even width or height, which have no center.
+    pub fn with_center_dot(self) -> Result<Self, String> {
+        let (box_width, box_height) = self.box_shape.ok_or("Center dot needs boxes")?;
+        if box_width % 2 == 0 || box_height % 2 == 0 {
+            return Err(format!("{}x{} boxes have no center", box_width, box_height));
+        }
+        let center = self.disjoint_group((box_width, box_height), box_height / 2, box_width / 2);
+        self.with_houses(RegionType::CenterDot, vec![center])
+    }
+
+    /// Adds the disjoint groups: for every position in a box, the cells at that position.
+    /// Returns an error for jigsaw regions.
+    pub fn with_disjoint_groups(self) -> Result<Self, String> {
+        let shape = self.box_shape.ok_or("Disjoint groups need boxes")?;
+        let groups: Vec<Vec<(usize, usize)>> = (0..self.size)
+            .map(|k| self.disjoint_group(shape, k / shape.0, k % shape.0))
+            .collect();
+        self.with_houses(RegionType::DisjointGroup, groups)
+    }
+
+    /// The number of rows, columns and numbers
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Width and height of the boxes, None for the regions of a jigsaw sudoku
+    pub fn box_shape(&self) -> Option<(usize, usize)> {
+        self.box_shape
+    }
+
+    /// Rows, columns, regions and extra houses, in the order they were added
+    pub fn houses(&self) -> &[Vec<(usize, usize)>] {
+        &self.houses
+    }
+
+    /// The cells of a region (a box or a jigsaw shape) in row-major order
+    pub fn region(&self, region: usize) -> &[(usize, usize)] {
+        &self.houses[2 * self.size + region]
+    }
+
+    /// The index of the region that contains the cell
+    pub fn region_of(&self, r: usize, c: usize) -> usize {
+        self.regions[r * self.size + c]
+    }
+
+    /// The other cells that share a house with the cell
+    pub fn peers(&self, r: usize, c: usize) -> &[(usize, usize)] {
+        &self.peers[r * self.size + c]
+    }
+
+    /// Coordinates (row, column) of all cells in row-major order
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let n = self.size;
+        (0..n * n).map(move |i| (i / n, i % n))
     }
 }
 
-impl Sudoku<NumberSet> {
-    pub fn is_solved(&self) -> bool {
-        for i in Ix::all_indices() {
-            let mut seen = NumberSet::empty();
-            for cell in self.row(i) {
-                // check that the cell has exactly one number
-                if !cell.is_singleton() {
-                    return false;
-                }
-                seen = seen | *cell;
-            }
-            if seen != NumberSet::all() {
-                return false;
-            }
-            seen = NumberSet::empty();
-            for cell in self.col(i) {
-                seen = seen | *cell;
-            }
-            if seen != NumberSet::all() {
-                return false;
-            }
-            seen = NumberSet::empty();
-            for cell in self.block(i) {
-                seen = seen | *cell;
-            }
-            if seen != NumberSet::all() {
-                return false;
+/// Returns true if the cells are connected by horizontal and vertical steps
+fn is_connected(cells: &[(usize, usize)]) -> bool {
+    let mut reached = vec![false; cells.len()];
+    let mut todo = vec![0];
+    reached[0] = true;
+    while let Some(i) = todo.pop() {
+        let (r, c) = cells[i];
+        for (j, &(r2, c2)) in cells.iter().enumerate() {
+            let adjacent = (r == r2 && (c + 1 == c2 || c2 + 1 == c))
+                || (c == c2 && (r + 1 == r2 || r2 + 1 == r));
+            if adjacent && !reached[j] {
+                reached[j] = true;
+                todo.push(j);
             }
         }
-        true
     }
+    reached.into_iter().all(|x| x)
+}
 
-    /// Checks if any number occurs twice in a region or if there are any empty cells.
-    pub fn is_invalid(&self) -> bool {
-        for i in Ix::all_indices() {
-            let mut seen = NumberSet::empty();
-            for cell in self.row(i) {
-                if cell.is_singleton() {
-                    if cell.intersects(seen) {
-                        return true;
-                    }
-                    seen = seen | *cell;
-                } else if cell.is_empty() {
-                    return true;
-                }
-            }
-            seen = NumberSet::empty();
-            for cell in self.col(i) {
-                if cell.is_singleton() {
-                    if cell.intersects(seen) {
-                        return true;
-                    }
-                    seen = seen | *cell;
-                } else if cell.is_empty() {
-                    return true;
-                }
-            }
-            seen = NumberSet::empty();
-            for cell in self.block(i) {
-                if cell.is_singleton() {
-                    if cell.intersects(seen) {
-                        return true;
-                    }
-                    seen = seen | *cell;
-                } else if cell.is_empty() {
-                    return true;
-                }
-            }
+lazy_static! {
+    // every distinct layout that was interned, see `Layout::intern`
+    static ref LAYOUTS: Mutex<Vec<&'static Layout>> = Mutex::new(Vec::new());
+    // the layout of a `Sudoku` unless it is replaced
+    static ref SUDOKU_LAYOUT: &'static Layout = Layout::boxes(3, 3).intern();
+}
+
+impl Layout {
+    /// An equal layout that lives for the rest of the program. Each distinct layout is
+    /// allocated only once, so boards can refer to it and stay cheap to copy.
+    pub fn intern(self) -> &'static Layout {
+        let mut layouts = LAYOUTS.lock().unwrap();
+        if let Some(&layout) = layouts.iter().find(|&&layout| *layout == self) {
+            return layout;
         }
-        false
+        let layout: &'static Layout = Box::leak(Box::new(self));
+        layouts.push(layout);
+        layout
     }
+}
 
-    /* pub fn is_unsolved(&self) -> bool {
-        !self.is_solved && !self.contradiction
-    } */
+/// Candidates removed from a cell by a deduction
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Elimination {
+    pub row: Ix,
+    pub col: Ix,
+    pub numbers: NumberSet,
+}
 
-    pub fn all_numbers_possible(&self) -> bool {
-        //check that any number is possible in all rows, cols and blocks
-        for i in Ix::all_indices() {
-            let mut seen_row = NumberSet::empty();
-            let mut seen_col = NumberSet::empty();
-            let mut seen_block = NumberSet::empty();
-            for cell in self.row(i) {
-                seen_row = seen_row | *cell;
-            }
-            if seen_row != NumberSet::all() {
-                return false;
-            }
-            for cell in self.col(i) {
-                seen_col = seen_col | *cell;
-            }
-            if seen_col != NumberSet::all() {
-                return false;
-            }
-            for cell in self.block(i) {
-                seen_block = seen_block | *cell;
-            }
-            if seen_block != NumberSet::all() {
-                return false;
-            }
+/// A single candidate number in a cell
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Candidate {
+    pub row: Ix,
+    pub col: Ix,
+    pub number: NumberSet,
+}
+
+impl Candidate {
+    pub fn new(row: Ix, col: Ix, number: NumberSet) -> Self {
+        Self { row, col, number }
+    }
+
+    pub fn cell(&self) -> (Ix, Ix) {
+        (self.row, self.col)
+    }
+}
+
+/// Name of a cell in row/column notation, e.g. "r3c5"
+pub fn cell_name((r, c): (Ix, Ix)) -> String {
+    format!("r{}c{}", usize::from(r) + 1, usize::from(c) + 1)
+}
+
+/// Parses a cell in row/column notation, e.g. "r3c5"
+pub fn parse_cell(s: &str) -> Result<(Ix, Ix), String> {
+    let digit = |ch: Option<char>| {
+        ch.and_then(|ch| ch.to_digit(10))
+            .filter(|&d| (1..=9).contains(&d))
+            .map(|d| Ix::ALL_INDICES[d as usize - 1])
+    };
+    let mut chars = s.chars();
+    match (
+        chars.next(),
+        digit(chars.next()),
+        chars.next(),
+        digit(chars.next()),
+        chars.next(),
+    ) {
+        (Some('r'), Some(r), Some('c'), Some(c), None) => Ok((r, c)),
+        _ => Err(format!("Not a valid cell: {}", s)),
+    }
+}
+
+/// Eureka notation, e.g. "(7)r3c5"
+impl Display for Candidate {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(formatter, "({}){}", self.number, cell_name(self.cell()))
+    }
+}
+
+impl FromStr for Candidate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("Not a valid candidate: {}", s);
+        let rest = s.strip_prefix('(').ok_or_else(err)?;
+        let mut chars = rest.chars();
+        let number = chars
+            .next()
+            .filter(|&ch| ch != ' ')
+            .map(NumberSet::try_from)
+            .ok_or_else(err)??;
+        let cell = chars.as_str().strip_prefix(')').ok_or_else(err)?;
+        let (row, col) = parse_cell(cell)?;
+        Ok(Candidate::new(row, col, number))
+    }
+}
+
+impl Sudoku<NumberSet> {
+    /// What removing numbers from the candidates of a cell would eliminate, if anything
+    pub fn elimination(&self, r: Ix, c: Ix, numbers: NumberSet) -> Option<Elimination> {
+        let removed = *self.get(r, c) & numbers;
+        if removed.is_empty() {
+            return None;
+        }
+        Some(Elimination {
+            row: r,
+            col: c,
+            numbers: removed,
+        })
+    }
+
+    /// Removes numbers from the candidates of a cell, returns what was actually removed
+    pub fn eliminate(&mut self, r: Ix, c: Ix, numbers: NumberSet) -> Option<Elimination> {
+        let elimination = self.elimination(r, c, numbers)?;
+        *self.get_mut(r, c) -= elimination.numbers;
+        Some(elimination)
+    }
+
+    /// Removes all given candidates
+    pub fn apply_eliminations<'a>(
+        &mut self,
+        eliminations: impl IntoIterator<Item = &'a Elimination>,
+    ) {
+        for e in eliminations {
+            *self.get_mut(e.row, e.col) -= e.numbers;
         }
-        true
     }
 
-    pub fn has_no_contradiction(&self) -> bool {
-        //check that any number is possible in all rows, cols and blocks
-        //check that any set number is only in one cell in every row, col and block
+    /// Checks that every cell still has the number of the given solution as a candidate
+    pub fn is_consistent_with(&self, solution: &Sudoku<NumberSet>) -> bool {
+        self.iter()
+            .zip(solution.iter())
+            .all(|(cell, n)| cell.contains(*n))
+    }
+
+    /// The candidates of a cell that are not already set in one of its peers
+    pub fn remaining_values(&self, r: Ix, c: Ix) -> NumberSet {
+        Board::remaining_values(self, usize::from(r), usize::from(c))
+    }
+}
+
+/// A set of candidate numbers: `NumberSet` for `Sudoku`, `grid::Candidates` for grids of any
+/// size
+pub trait CandidateSet:
+    Copy + PartialEq + BitOr<Output = Self> + BitAnd<Output = Self> + Sub<Output = Self>
+{
+    const NONE: Self;
+
+    /// Returns true if exactly one number is set
+    fn is_singleton(&self) -> bool;
+
+    /// Number of numbers set
+    fn len(&self) -> usize;
+
+    /// The numbers in this set, each as a singleton set
+    fn singletons(self) -> impl Iterator<Item = Self>;
+
+    fn is_empty(&self) -> bool {
+        *self == Self::NONE
+    }
+}
+
+impl CandidateSet for NumberSet {
+    const NONE: Self = NumberSet::NONE;
+
+    fn is_singleton(&self) -> bool {
+        NumberSet::is_singleton(self)
+    }
+
+    fn len(&self) -> usize {
+        NumberSet::len(self)
+    }
+
+    fn singletons(self) -> impl Iterator<Item = Self> {
+        self.iter()
+    }
+}
+
+/// A grid of candidate sets whose houses are given by a `Layout`
+///
+/// The validity checks, `compute_exclude`, `compute_take_cell` and the solvers are written
+/// against this trait, so the 9x9 `Sudoku<NumberSet>` and the `grid::Grid` of any size share
+/// them. Cells are addressed by row and column from 0 to n - 1.
+pub trait Board: Clone + PartialEq + Display {
+    type Set: CandidateSet;
+
+    fn layout(&self) -> &'static Layout;
+
+    fn cell(&self, r: usize, c: usize) -> Self::Set;
+
+    fn cell_mut(&mut self, r: usize, c: usize) -> &mut Self::Set;
+
+    /// The set of all numbers, 1 to n
+    fn all_numbers(&self) -> Self::Set;
+
+    /// The candidates of a cell that are not already set in one of its peers
+    fn remaining_values(&self, r: usize, c: usize) -> Self::Set {
+        let placed = self
+            .layout()
+            .peers(r, c)
+            .iter()
+            .map(|&(r, c)| self.cell(r, c))
+            .filter(|cell| cell.is_singleton())
+            .fold(Self::Set::NONE, |placed, cell| placed | cell);
+        self.cell(r, c) - placed
+    }
+
+    fn is_solved(&self) -> bool {
+        // check that every cell has exactly one number
+        self.layout().positions().all(|(r, c)| self.cell(r, c).is_singleton())
+            && self.all_numbers_possible()
+    }
+
+    /// Checks if any number occurs twice in a house or if there are any empty cells.
+    fn is_invalid(&self) -> bool {
+        self.layout().positions().any(|(r, c)| self.cell(r, c).is_empty())
+            || self
+                .layout()
+                .houses()
+                .iter()
+                .any(|house| has_duplicate(house.iter().map(|&(r, c)| self.cell(r, c))))
+    }
+
+    fn all_numbers_possible(&self) -> bool {
+        //check that any number is possible in every house
+        self.layout().houses().iter().all(|house| {
+            house
+                .iter()
+                .fold(Self::Set::NONE, |seen, &(r, c)| seen | self.cell(r, c))
+                == self.all_numbers()
+        })
+    }
+
+    fn has_no_contradiction(&self) -> bool {
+        //check that any number is possible in every house
+        //check that any set number is only in one cell in every house
         //check that any cell has min one number
-        for i in Ix::all_indices() {
-            let mut seen_row = NumberSet::empty();
-            let mut seen_col = NumberSet::empty();
-            let mut seen_block = NumberSet::empty();
-            let mut seen_row_set = NumberSet::empty();
-            let mut seen_col_set = NumberSet::empty();
-            let mut seen_block_set = NumberSet::empty();
-            for cell in self.row(i) {
-                if *cell == NumberSet::empty() {
-                    return false;
-                }
-                if cell.is_singleton() {
-                    if *cell & seen_row_set != NumberSet::empty() {
-                        //number was set before
-                        return false;
-                    } else {
-                        seen_row_set = seen_row_set | *cell;
-                    }
-                }
-                seen_row = seen_row | *cell;
-            }
-            if seen_row != NumberSet::all() {
-                return false;
-            }
-            for cell in self.col(i) {
-                if *cell == NumberSet::empty() {
-                    return false;
-                }
-                if cell.is_singleton() {
-                    if *cell & seen_col_set != NumberSet::empty() {
-                        //number was set before
-                        return false;
-                    } else {
-                        seen_col_set = seen_col_set | *cell;
-                    }
-                }
-                seen_col = seen_col | *cell;
-            }
-            if seen_col != NumberSet::all() {
-                return false;
-            }
-            for cell in self.block(i) {
-                if *cell == NumberSet::empty() {
-                    return false;
-                }
-                if cell.is_singleton() {
-                    if *cell & seen_block_set != NumberSet::empty() {
-                        //number was set before
-                        return false;
-                    } else {
-                        seen_block_set = seen_block_set | *cell;
-                    }
-                }
-                seen_block = seen_block | *cell;
-            }
-            if seen_block != NumberSet::all() {
-                return false;
-            }
+        !self.is_invalid() && self.all_numbers_possible()
+    }
+}
+
+impl Board for Sudoku<NumberSet> {
+    type Set = NumberSet;
+
+    fn layout(&self) -> &'static Layout {
+        self.layout
+    }
+
+    fn cell(&self, r: usize, c: usize) -> NumberSet {
+        self.arr[r][c]
+    }
+
+    fn cell_mut(&mut self, r: usize, c: usize) -> &mut NumberSet {
+        &mut self.arr[r][c]
+    }
+
+    fn all_numbers(&self) -> NumberSet {
+        NumberSet::ALL
+    }
+}
+
+/// All subsets of `items` with exactly `k` elements, in lexicographic order
+pub(crate) fn combinations<T: Clone>(items: &[T], k: usize) -> Vec<Vec<T>> {
+    let mut result = Vec::new();
+    if k > items.len() {
+        return result;
+    }
+    let mut idx: Vec<usize> = (0..k).collect();
+    loop {
+        result.push(idx.iter().map(|&i| items[i].clone()).collect());
+        // advance the rightmost index that can still be increased
+        let mut i = k;
+        while i > 0 && idx[i - 1] == items.len() - k + i - 1 {
+            i -= 1;
+        }
+        if i == 0 {
+            return result;
+        }
+        idx[i - 1] += 1;
+        for j in i..k {
+            idx[j] = idx[j - 1] + 1;
         }
-        true
     }
 }
 
+/// Checks if a set number occurs more than once in a region
+fn has_duplicate<S: CandidateSet>(region: impl Iterator<Item = S>) -> bool {
+    let mut seen = S::NONE;
+    for cell in region.filter(|cell| cell.is_singleton()) {
+        if cell & seen != S::NONE {
+            //number was set before
+            return true;
+        }
+        seen = seen | cell;
+    }
+    false
+}
+
+/// The lines between the blocks are only drawn for 3x3 blocks
 impl Display for Sudoku<NumberSet> {
     fn fmt(&self, mut formatter: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let boxes = self.layout.box_shape() == Some((3, 3));
         for row in 0..9 {
             for col in 0..9 {
-                if col == 3 || col == 6 {
+                if boxes && (col == 3 || col == 6) {
                     write!(&mut formatter, "│")?;
                 }
                 write!(&mut formatter, "{}", self.arr[row][col])?;
             }
-            if row == 2 || row == 5 {
+            if boxes && (row == 2 || row == 5) {
                 writeln!(&mut formatter, "\n───┼───┼───")?;
             } else {
-                writeln!(&mut formatter, "")?;
+                writeln!(&mut formatter)?;
             }
         }
         Ok(())
@@ -500,7 +1106,42 @@ impl FromStr for Sudoku<NumberSet> {
                 arr[row][col] = NumberSet::try_from(value)?;
             }
         }
-        Ok(Sudoku { arr })
+        Ok(Sudoku {
+            arr,
+            layout: *SUDOKU_LAYOUT,
+        })
+    }
+}
+
+impl Sudoku<NumberSet> {
+    /// Parses the common single line format: 81 characters in row-major order,
+    /// where '.' or '0' is an empty cell
+    pub fn from_line(s: &str) -> Result<Self, String> {
+        let chars: Vec<_> = s.trim().chars().collect();
+        if chars.len() != SUDOKUSIZE * SUDOKUSIZE {
+            return Err(format!("Expected 81 cells, got {}", chars.len()));
+        }
+        let mut arr = [[NumberSet::all(); 9]; 9];
+        for (cell, &value) in arr.iter_mut().flat_map(|row| row.iter_mut()).zip(&chars) {
+            *cell = match value {
+                '.' | '0' => NumberSet::all(),
+                x => NumberSet::try_from(x)?,
+            };
+        }
+        Ok(Sudoku {
+            arr,
+            layout: *SUDOKU_LAYOUT,
+        })
+    }
+
+    /// Formats the sudoku in the single line format, unsolved cells are written as '.'
+    pub fn to_line(&self) -> String {
+        self.iter()
+            .map(|cell| match cell.is_singleton() {
+                true => cell.to_string(),
+                false => ".".to_string(),
+            })
+            .collect()
     }
 }
 
@@ -524,9 +1165,9 @@ pub fn parse_game_state(input: &str) -> Result<Sudoku<GameStateCell>, String> {
     for line in input.lines() {
         let game_state_line: Vec<GameStateCell> = line
             .chars()
-            .filter(|x| charsetstr.find(x.to_owned()) != None)
+            .filter(|x| charsetstr.find(x.to_owned()).is_some())
             .map(|ch| {
-                let cell = if emptyset.find(ch) != None {
+                if emptyset.find(ch).is_some() {
                     None
                 } else {
                     match ch {
@@ -541,11 +1182,10 @@ pub fn parse_game_state(input: &str) -> Result<Sudoku<GameStateCell>, String> {
                         '9' => Some(9),
                         _ => panic!("Unhandled character."),
                     }
-                };
-                cell
+                }
             })
             .collect();
-        if game_state_line.len() > 0 {
+        if !game_state_line.is_empty() {
             game_state_vec.push(game_state_line)
         }
     }
@@ -554,12 +1194,11 @@ pub fn parse_game_state(input: &str) -> Result<Sudoku<GameStateCell>, String> {
     }*/
     let mut game_state = Sudoku::<GameStateCell> {
         arr: [[None; SUDOKUSIZE]; SUDOKUSIZE],
+        layout: *SUDOKU_LAYOUT,
     };
-    use std::cmp::min;
-    for i in 0..min(game_state_vec.len(), SUDOKUSIZE) {
-        let line_len = game_state_vec[i].len();
-        for j in 0..min(line_len, SUDOKUSIZE) {
-            game_state.arr[i][j] = game_state_vec[i][j];
+    for (row, line) in game_state.arr.iter_mut().zip(game_state_vec.iter()) {
+        for (cell, &value) in row.iter_mut().zip(line.iter()) {
+            *cell = value;
         }
     }
     // TODO: define error conditions
@@ -589,11 +1228,11 @@ pub fn format_game_state(game_state: &Sudoku<GameStateCell>) -> String {
 
 /// Known issues:
 /// - does not use iterators
-/// - should be in impl Sudoku<>
-/// (this also applies to other functions)
+/// - should be in impl Sudoku<> (this also applies to other functions)
 pub fn game_to_solver_state(game_state: &Sudoku<GameStateCell>) -> Sudoku<NumberSet> {
     let mut solver_state = Sudoku::<NumberSet> {
         arr: [[NumberSet::NONE; SUDOKUSIZE]; SUDOKUSIZE],
+        layout: game_state.layout,
     };
     for i in 0..SUDOKUSIZE {
         for j in 0..SUDOKUSIZE {
@@ -620,6 +1259,7 @@ pub fn game_to_solver_state(game_state: &Sudoku<GameStateCell>) -> Sudoku<Number
 pub fn solver_to_game_state(solver_state: &Sudoku<NumberSet>) -> Sudoku<GameStateCell> {
     let mut game_state = Sudoku::<GameStateCell> {
         arr: [[None; SUDOKUSIZE]; SUDOKUSIZE],
+        layout: solver_state.layout,
     };
     for i in 0..SUDOKUSIZE {
         for j in 0..SUDOKUSIZE {
@@ -640,53 +1280,22 @@ pub fn solver_to_game_state(solver_state: &Sudoku<NumberSet>) -> Sudoku<GameStat
     game_state
 }
 
-// conversion of field indices: row/column to outer_square/inner_square
-fn ij2sk(i: usize, j: usize) -> (usize, usize) {
-    let s = i / 3 * 3 + j / 3;
-    let k = i % 3 * 3 + j % 3;
-    return (s, k);
-}
-
-// conversion of field indices: outer_square/inner_square to row/column
-fn sk2ij(s: usize, k: usize) -> (usize, usize) {
-    let i = s / 3 * 3 + k / 3;
-    let j = s % 3 * 3 + k % 3;
-    return (i, j);
-}
-
 /// compute field constraints: use known fields to remove options
 ///
 /// Strategy:
 /// 1. iterate over all cells to finde known cell
-/// 2. for every known cell, remove value from every
-///   - row
-///   - column
-///   - square
-pub fn compute_exclude(solver_state: &mut Sudoku<NumberSet>) {
-    for i in 0..SUDOKUSIZE {
-        for j in 0..SUDOKUSIZE {
-            let cell_num_set = solver_state.arr[i][j];
-            if cell_num_set.is_singleton() {
-                for k in 0..SUDOKUSIZE {
-                    // row
-                    if k != j {
-                        solver_state.arr[i][k] = solver_state.arr[i][k] - cell_num_set;
-                    }
-                }
-                for k in 0..SUDOKUSIZE {
-                    // column
-                    if k != i {
-                        solver_state.arr[k][j] = solver_state.arr[k][j] - cell_num_set;
-                    }
-                }
-                let (sq, sqi) = ij2sk(i, j);
-                for k in 0..SUDOKUSIZE {
-                    // square
-                    if k != sqi {
-                        let (i2, j2) = sk2ij(sq, k);
-                        solver_state.arr[i2][j2] = solver_state.arr[i2][j2] - cell_num_set;
-                    }
-                }
+/// 2. for every known cell, remove value from every peer, i.e. the other cells of
+///   - its row
+///   - its column
+///   - its block (and any other house of the layout)
+pub fn compute_exclude<B: Board>(solver_state: &mut B) {
+    let layout = solver_state.layout();
+    for (i, j) in layout.positions() {
+        let cell_num_set = solver_state.cell(i, j);
+        if cell_num_set.is_singleton() {
+            for &(i2, j2) in layout.peers(i, j) {
+                let cell = solver_state.cell_mut(i2, j2);
+                *cell = *cell - cell_num_set;
             }
         }
     }
@@ -695,145 +1304,196 @@ pub fn compute_exclude(solver_state: &mut Sudoku<NumberSet>) {
 /// compute field constraints: assign known field when it's the only option
 ///     
 /// Strategy:
-/// - scan every row, column, square:
-/// - count how often each number occours in every row (/column/square)
-/// - if a number only occours once in a row (/...), assign it to this cell
-pub fn compute_take_cell(solver_state: &mut Sudoku<NumberSet>) {
-    for i in 0..SUDOKUSIZE {
-        // row
-        let mut cnt = [0u32; SUDOKUSIZE];
-        let mut pos: [Option<usize>; SUDOKUSIZE] = [None; SUDOKUSIZE];
-        for j in 0..SUDOKUSIZE {
-            // column
-            for num_idx in Ix::all_indices() {
-                if solver_state.arr[i][j] & NumberSet::from(num_idx) != NumberSet::NONE {
-                    // Nx exists in solver_state.arr[i][j]
-                    cnt[usize::from(num_idx)] += 1;
-                    pos[usize::from(num_idx)] = Some(j)
+/// - scan every house of the layout: rows, columns, blocks
+/// - count how often each number occours in every house
+/// - if a number only occours once in a house, assign it to this cell
+pub fn compute_take_cell<B: Board>(solver_state: &mut B) {
+    let numbers: Vec<B::Set> = solver_state.all_numbers().singletons().collect();
+    let mut cnt = vec![0u32; numbers.len()];
+    let mut pos: Vec<Option<(usize, usize)>> = vec![None; numbers.len()];
+    for house in solver_state.layout().houses() {
+        cnt.iter_mut().for_each(|n| *n = 0);
+        for &(i, j) in house.iter() {
+            for (num_idx, &number) in numbers.iter().enumerate() {
+                if solver_state.cell(i, j) & number != B::Set::NONE {
+                    // number exists in this cell
+                    cnt[num_idx] += 1;
+                    pos[num_idx] = Some((i, j))
                 };
             }
         }
-        for num_idx in Ix::all_indices() {
-            if cnt[usize::from(num_idx)] == 1 {
-                // this value is unique in our row, so it needs to be placed in that cell.
-                solver_state.arr[i][pos[usize::from(num_idx)].unwrap()] = NumberSet::from(num_idx)
+        for (num_idx, &number) in numbers.iter().enumerate() {
+            if cnt[num_idx] == 1 {
+                // this value is unique in our house, so it needs to be placed in that cell.
+                let (i, j) = pos[num_idx].unwrap();
+                *solver_state.cell_mut(i, j) = number;
             }
         }
     }
-    for j in 0..SUDOKUSIZE {
-        // TODO: column
-        let mut cnt = [0u32; SUDOKUSIZE];
-        let mut pos: [Option<usize>; SUDOKUSIZE] = [None; SUDOKUSIZE];
-        for i in 0..SUDOKUSIZE {
-            // column
-            for num_idx in Ix::all_indices() {
-                if solver_state.arr[i][j] & NumberSet::from(num_idx) != NumberSet::NONE {
-                    // Nx exists in solver_state.arr[i][j]
-                    cnt[usize::from(num_idx)] += 1;
-                    pos[usize::from(num_idx)] = Some(i)
-                };
-            }
-        }
-        for num_idx in Ix::all_indices() {
-            if cnt[usize::from(num_idx)] == 1 {
-                // this value is unique in our row, so it needs to be placed in that cell.
-                solver_state.arr[pos[usize::from(num_idx)].unwrap()][j] = NumberSet::from(num_idx)
+}
+
+/// Deductions that the solvers apply before and between guesses
+///
+/// `Singles` works on any board, `strategy::Pipeline` runs solving techniques on a sudoku.
+pub trait Propagation<B: Board> {
+    /// One deduction, as reported in the trace of the solvers
+    type Step;
+
+    /// Applies deductions until the board is solved, a contradiction shows up or nothing is
+    /// found. Returns them in the order they were applied.
+    fn propagate(&self, solver_state: &mut B) -> Vec<Self::Step>;
+
+    /// The step for trying a number in a cell without a logical reason
+    fn guess(&self, r: usize, c: usize, number: B::Set) -> Self::Step;
+}
+
+/// A cell solved by `Singles` or by a guess of the search
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Placement<S> {
+    pub row: usize,
+    pub col: usize,
+    pub number: S,
+    pub guess: bool,
+}
+
+/// Naked and hidden singles: `compute_exclude` and `compute_take_cell` until nothing changes
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Singles;
+
+impl<B: Board> Propagation<B> for Singles {
+    type Step = Placement<B::Set>;
+
+    fn propagate(&self, solver_state: &mut B) -> Vec<Self::Step> {
+        let mut steps = Vec::new();
+        loop {
+            let old_solver_state = solver_state.clone();
+            compute_exclude(solver_state);
+            compute_take_cell(solver_state);
+            steps.extend(solver_state.layout().positions().filter_map(|(r, c)| {
+                let cell = solver_state.cell(r, c);
+                let solved = cell.is_singleton() && !old_solver_state.cell(r, c).is_singleton();
+                if solved {
+                    Some(Placement {
+                        row: r,
+                        col: c,
+                        number: cell,
+                        guess: false,
+                    })
+                } else {
+                    None
+                }
+            }));
+            if old_solver_state == *solver_state
+                || solver_state.is_solved()
+                || !solver_state.has_no_contradiction()
+            {
+                break;
             }
         }
+        steps
     }
-    for s in 0..SUDOKUSIZE {
-        // square
-        let mut cnt = [0u32; SUDOKUSIZE];
-        let mut pos: [Option<usize>; SUDOKUSIZE] = [None; SUDOKUSIZE];
-        for k in 0..SUDOKUSIZE {
-            // column
-            let (i, j) = sk2ij(s, k);
-            for num_idx in Ix::all_indices() {
-                if solver_state.arr[i][j] & NumberSet::from(num_idx) != NumberSet::NONE {
-                    // Nx exists in solver_state.arr[i][j]
-                    cnt[usize::from(num_idx)] += 1;
-                    pos[usize::from(num_idx)] = Some(k)
-                };
-            }
+
+    fn guess(&self, r: usize, c: usize, number: B::Set) -> Self::Step {
+        Placement {
+            row: r,
+            col: c,
+            number,
+            guess: true,
         }
-        for num_idx in Ix::all_indices() {
-            if cnt[usize::from(num_idx)] == 1 {
-                let k = pos[usize::from(num_idx)].unwrap();
-                let (i, j) = sk2ij(s, k);
-                // this value is unique in our row, so it needs to be placed in that cell.
-                solver_state.arr[i][j] = NumberSet::from(num_idx)
-            }
+    }
+}
+
+// compute field constraints: propagate until nothing changes
+//
+// Returns won, lost and the steps in the order they were applied.
+pub fn compute_solve1<B: Board, P: Propagation<B>>(
+    solver_state: &mut B,
+    propagation: &P,
+) -> (bool, bool, Vec<P::Step>) {
+    let steps = propagation.propagate(solver_state);
+    let won = solver_state.is_solved();
+    let lost = !solver_state.has_no_contradiction();
+    (won, lost, steps)
+}
+
+/// Strategy for choosing the cell to branch on in the backtracking solvers
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CellSelection {
+    /// The first unsolved cell in row-major order
+    FirstEmpty,
+    /// The unsolved cell with the fewest remaining values (MRV)
+    MinRemaining,
+    /// Like MinRemaining, ties are broken by the highest number of unsolved peers
+    MinRemainingDegree,
+}
+
+impl CellSelection {
+    pub const ALL: [CellSelection; 3] = [
+        CellSelection::FirstEmpty,
+        CellSelection::MinRemaining,
+        CellSelection::MinRemainingDegree,
+    ];
+
+    /// Picks an unsolved cell to branch on, returns None if every cell is solved
+    pub fn select(self, solver_state: &Sudoku<NumberSet>) -> Option<(Ix, Ix)> {
+        let (r, c) = self.select_cell(solver_state)?;
+        Some((Ix::ALL_INDICES[r], Ix::ALL_INDICES[c]))
+    }
+
+    /// Like `select`, for a board of any size
+    pub fn select_cell<B: Board>(self, solver_state: &B) -> Option<(usize, usize)> {
+        let mut unsolved = solver_state
+            .layout()
+            .positions()
+            .filter(|&(r, c)| !solver_state.cell(r, c).is_singleton());
+        let remaining = |r, c| solver_state.remaining_values(r, c).len();
+        match self {
+            CellSelection::FirstEmpty => unsolved.next(),
+            CellSelection::MinRemaining => unsolved.min_by_key(|&(r, c)| remaining(r, c)),
+            CellSelection::MinRemainingDegree => unsolved.min_by_key(|&(r, c)| {
+                let degree = solver_state
+                    .layout()
+                    .peers(r, c)
+                    .iter()
+                    .filter(|&&(r, c)| !solver_state.cell(r, c).is_singleton())
+                    .count();
+                (remaining(r, c), std::cmp::Reverse(degree))
+            }),
         }
     }
 }
 
-// compute field constraints: iteratively exclude and take
-pub fn compute_solve1(solver_state: &mut Sudoku<NumberSet>, verbose: bool) -> (bool, bool) {
-    let mut i = 0u32;
-    let mut changed;
-    let mut won;
-    let mut lost;
-    loop {
-        let old_solver_state = solver_state.clone();
-        compute_exclude(solver_state);
-        compute_take_cell(solver_state);
-        i += 1;
-        changed = !(&old_solver_state == solver_state);
-        won = solver_state.is_solved();
-        lost = !solver_state.has_no_contradiction();
-        if changed & verbose {
-            let game_state = solver_to_game_state(&solver_state);
-            println!(
-                "reduced {}: {}{}{}",
-                i,
-                if changed { "changed " } else { "" },
-                if won { "won" } else { "" },
-                if lost { "lost" } else { "" }
-            );
-            println!("{}", format_game_state(&game_state));
-        }
-        if !changed | won | lost {
-            break;
-        }
-    }
-    return (won, lost);
-}
-
-// compute field constraints: simple depth-first search (with very simple strategy)
-pub fn compute_solve_tree(solver_state: &mut Sudoku<NumberSet>, verbose: bool) -> (bool, bool) {
-    let (won, lost) = compute_solve1(solver_state, verbose);
+// compute field constraints: depth-first search, propagating before every guess
+//
+// Returns won, lost and the steps that lead to the solution, with a guess step for every
+// branch taken on the way.
+pub fn compute_solve_tree<B: Board, P: Propagation<B>>(
+    solver_state: &mut B,
+    propagation: &P,
+    selection: CellSelection,
+) -> (bool, bool, Vec<P::Step>) {
+    let (won, lost, mut steps) = compute_solve1(solver_state, propagation);
     if won | lost {
-        return (won, lost);
+        return (won, lost, steps);
     }
-    for i in 0..SUDOKUSIZE {
-        for j in 0..SUDOKUSIZE {
-            let lenfij = solver_state.arr[i][j].bits().count_ones();
-            if lenfij > 1 {
-                for num_idx in Ix::all_indices() {
-                    if solver_state.arr[i][j] & NumberSet::from(num_idx) != NumberSet::NONE {
-                        // Nx exists in solver_state.arr[i][j]
-                        let mut work_state = solver_state.clone();
-                        work_state.arr[i][j] = NumberSet::from(num_idx); // try this move
-                        if verbose {
-                            println!("move:");
-                            let game_state = solver_to_game_state(&solver_state);
-                            println!("{}", format_game_state(&game_state));
-                        }
-                        let (won, lost) = compute_solve_tree(&mut work_state, verbose);
-                        if won {
-                            *solver_state = work_state;
-                            return (won, lost);
-                        }
-                    };
-                }
+    // every solution has one of the candidates of the selected cell, so one cell suffices
+    if let Some((i, j)) = selection.select_cell(solver_state) {
+        for number in solver_state.cell(i, j).singletons() {
+            let mut work_state = solver_state.clone();
+            *work_state.cell_mut(i, j) = number; // try this move
+            let (won, lost, branch_steps) =
+                compute_solve_tree(&mut work_state, propagation, selection);
+            if won {
+                *solver_state = work_state;
+                steps.push(propagation.guess(i, j, number));
+                steps.extend(branch_steps);
+                return (won, lost, steps);
             }
         }
     }
 
     // tried all moves, but none was successfull
-    let won = false;
-    let lost = true;
-    return (won, lost);
+    (false, true, steps)
 }
 
 #[cfg(test)]
@@ -842,7 +1502,8 @@ mod tests {
     use lazy_static::lazy_static;
     use std::collections::HashSet;
 
-    const SUDOKU: Sudoku<u8> = Sudoku {
+    lazy_static! {
+        static ref SUDOKU: Sudoku<u8> = Sudoku {
         arr: [
             [11, 12, 13, 14, 15, 16, 17, 18, 19],
             [21, 22, 23, 24, 25, 26, 27, 28, 29],
@@ -854,7 +1515,9 @@ mod tests {
             [81, 82, 83, 84, 85, 86, 87, 88, 89],
             [91, 92, 93, 94, 95, 96, 97, 98, 99],
         ],
-    };
+        layout: *SUDOKU_LAYOUT,
+        };
+    }
 
     #[test]
     fn test_row_iter() {
@@ -874,6 +1537,83 @@ mod tests {
         let expected: HashSet<_> = [14, 15, 16, 24, 25, 26, 34, 35, 36].iter().collect();
         assert_eq!(block2, expected);
     }
+    #[test]
+    fn test_houses() {
+        let houses: Vec<_> = SUDOKU.houses().collect();
+        assert_eq!(houses.len(), 27);
+        assert_eq!(houses[10].kind(), RegionType::Col);
+        assert_eq!(houses[10].index(), Ix2);
+        let block2: Vec<_> = SUDOKU.house(houses[19]).cloned().collect();
+        assert_eq!(block2, vec![14, 15, 16, 24, 25, 26, 34, 35, 36]);
+        for house in House::for_cell(Ix5, Ix8).iter() {
+            assert!(house.contains(Ix5, Ix8));
+            assert!(house.cells().contains(&(Ix5, Ix8)));
+        }
+        let regions: Vec<Vec<_>> = SUDOKU.regions().map(|r| r.cloned().collect()).collect();
+        assert_eq!(regions[19], block2);
+    }
+    #[test]
+    fn test_house_mut() {
+        let mut sudoku = *SUDOKU;
+        for cell in sudoku.house_mut(House::block(Ix2)) {
+            *cell = 0;
+        }
+        let changed: Vec<_> = sudoku.iter().filter(|&&cell| cell == 0).collect();
+        assert_eq!(changed.len(), 9);
+        assert!(sudoku.block(Ix2).all(|&cell| cell == 0));
+    }
+
+    #[test]
+    fn test_jigsaw_layout() {
+        let layout = Layout::jigsaw(9, crate::examples::JIGSAW_REGIONS).unwrap();
+        let sudoku = SUDOKU.with_layout(layout).unwrap();
+        // the first region takes r2c4 and two cells of the third row
+        let block1: Vec<_> = sudoku.block(Ix1).cloned().collect();
+        assert_eq!(block1, vec![11, 12, 13, 21, 22, 23, 24, 32, 33]);
+        assert!(sudoku.block_for_cell(Ix2, Ix4).eq(sudoku.block(Ix1)));
+        assert!(sudoku.sees((Ix1, Ix1), (Ix2, Ix4)));
+        assert!(!SUDOKU.sees((Ix1, Ix1), (Ix2, Ix4)));
+        let houses: Vec<House> = sudoku.houses_for_cell(Ix2, Ix4).collect();
+        assert_eq!(houses.len(), 3);
+        assert_eq!(houses[2].cells()[6], (Ix2, Ix4));
+        assert!(SUDOKU.with_layout(Layout::boxes(2, 2)).is_err());
+    }
+
+    #[test]
+    fn test_extra_houses() {
+        let layout = Layout::boxes(3, 3).with_diagonals().unwrap();
+        let sudoku = SUDOKU.with_layout(layout.clone()).unwrap();
+        let houses: Vec<House> = sudoku.houses().collect();
+        assert_eq!(houses.len(), 29);
+        assert_eq!(houses[28].kind(), RegionType::Diagonal);
+        assert_eq!(houses[28].to_string(), "diagonal 2");
+        let anti_diagonal: Vec<_> = sudoku.house(houses[28]).cloned().collect();
+        assert_eq!(anti_diagonal, vec![19, 28, 37, 46, 55, 64, 73, 82, 91]);
+        assert_eq!(sudoku.houses_for_cell(Ix5, Ix5).count(), 5);
+        assert!(sudoku.sees((Ix1, Ix1), (Ix9, Ix9)));
+
+        // the solution of the plain sudoku breaks the diagonals
+        let solved = Sudoku::from_str(crate::examples::SOLVED_SUDOKU).unwrap();
+        assert!(solved.is_solved());
+        let solved = solved.with_layout(layout).unwrap();
+        assert!(!solved.is_solved());
+        assert!(solved.is_invalid());
+
+        // every house needs an index
+        let rows = (0..10).map(|k| (0..9).map(|c| (k % 9, c)).collect::<Vec<_>>());
+        let layout = Layout::boxes(3, 3).with_houses(RegionType::Window, rows);
+        assert!(SUDOKU.with_layout(layout.unwrap()).is_err());
+
+        // the cells of a house are row-major in whatever order they were given
+        let reversed = vec![(0..9).rev().map(|c| (0, c)).collect::<Vec<_>>()];
+        let layout = Layout::boxes(3, 3).with_houses(RegionType::Window, reversed);
+        let mut sudoku = SUDOKU.with_layout(layout.unwrap()).unwrap();
+        let window = sudoku.houses().last().unwrap();
+        assert_eq!(window.cells()[0], (Ix1, Ix1));
+        let cells: Vec<_> = sudoku.house(window).cloned().collect();
+        let cells_mut: Vec<_> = sudoku.house_mut(window).map(|cell| *cell).collect();
+        assert_eq!(cells, cells_mut);
+    }
 
     const N1: NumberSet = NumberSet::N1;
     const N2: NumberSet = NumberSet::N2;
@@ -885,7 +1625,8 @@ mod tests {
     const N8: NumberSet = NumberSet::N8;
     const N9: NumberSet = NumberSet::N9;
     const NALL: NumberSet = NumberSet::all();
-    const VALID_SUDOKU: Sudoku<NumberSet> = Sudoku {
+    lazy_static! {
+        static ref VALID_SUDOKU: Sudoku<NumberSet> = Sudoku {
         arr: [
             [N5, N6, N3, N2, N1, N7, N9, N8, N4],
             [N9, N8, N1, N3, N4, N6, N7, N5, N2],
@@ -897,8 +1638,11 @@ mod tests {
             [N6, N7, N9, N8, N3, N1, N4, N2, N5],
             [N8, N5, N2, N6, N9, N4, N3, N1, N7],
         ],
-    };
-    const CONTRADICTION_SUDOKU1: Sudoku<NumberSet> = Sudoku {
+        layout: *SUDOKU_LAYOUT,
+        };
+    }
+    lazy_static! {
+        static ref CONTRADICTION_SUDOKU1: Sudoku<NumberSet> = Sudoku {
         //empty cell
         arr: [
             [N5, N6, N3, N2, N1, N7, N9, N8, N4],
@@ -911,7 +1655,9 @@ mod tests {
             [N6, N7, N9, N8, N3, NumberSet::empty(), N4, N2, N5],
             [N8, N5, N2, N6, N9, N4, N3, N1, N7],
         ],
-    };
+        layout: *SUDOKU_LAYOUT,
+        };
+    }
     lazy_static! {
         static ref CONTRADICTION_SUDOKU2: Sudoku<NumberSet> = Sudoku {
         //number not in row/block
@@ -926,6 +1672,7 @@ mod tests {
             [N6, N7, N9, N8, N3, N1, N4, N2, N5],
             [N8, N5, N2, N6, N9, N4, N3, N1, N7],
         ],
+        layout: *SUDOKU_LAYOUT,
         };
     }
     lazy_static! {
@@ -942,62 +1689,41 @@ mod tests {
             [N6, N7, N9, NALL, N3, NALL, NALL, N2, N5],
             [NALL, NALL, NALL, NALL, NALL, NALL, N6, N1, N7],
         ],
+        layout: *SUDOKU_LAYOUT,
         };
     }
     #[test]
     fn test_is_solved() {
-        assert_eq!(VALID_SUDOKU.is_solved(), true);
-        assert_eq!(CONTRADICTION_SUDOKU1.is_solved(), false);
-        assert_eq!(CONTRADICTION_SUDOKU2.is_solved(), false);
-        assert_eq!(CONTRADICTION_SUDOKU3.is_solved(), false);
+        assert!(VALID_SUDOKU.is_solved());
+        assert!(!CONTRADICTION_SUDOKU1.is_solved());
+        assert!(!CONTRADICTION_SUDOKU2.is_solved());
+        assert!(!CONTRADICTION_SUDOKU3.is_solved());
     }
     #[test]
     fn test_is_invalid() {
-        assert_eq!(VALID_SUDOKU.is_invalid(), false);
-        assert_eq!(CONTRADICTION_SUDOKU1.is_invalid(), true);
-        assert_eq!(CONTRADICTION_SUDOKU2.is_invalid(), false);
-        assert_eq!(CONTRADICTION_SUDOKU3.is_invalid(), true);
+        assert!(!VALID_SUDOKU.is_invalid());
+        assert!(CONTRADICTION_SUDOKU1.is_invalid());
+        assert!(!CONTRADICTION_SUDOKU2.is_invalid());
+        assert!(CONTRADICTION_SUDOKU3.is_invalid());
     }
     #[test]
     fn test_has_no_contradiction() {
-        assert_eq!(VALID_SUDOKU.has_no_contradiction(), true);
-        assert_eq!(CONTRADICTION_SUDOKU1.has_no_contradiction(), false);
-        assert_eq!(CONTRADICTION_SUDOKU2.has_no_contradiction(), false);
-        assert_eq!(CONTRADICTION_SUDOKU3.has_no_contradiction(), false);
+        assert!(VALID_SUDOKU.has_no_contradiction());
+        assert!(!CONTRADICTION_SUDOKU1.has_no_contradiction());
+        assert!(!CONTRADICTION_SUDOKU2.has_no_contradiction());
+        assert!(!CONTRADICTION_SUDOKU3.has_no_contradiction());
     }
     #[test]
     fn test_all_numbers_possible() {
-        assert_eq!(VALID_SUDOKU.all_numbers_possible(), true);
-        assert_eq!(CONTRADICTION_SUDOKU1.all_numbers_possible(), false);
-        assert_eq!(CONTRADICTION_SUDOKU2.all_numbers_possible(), false);
-        assert_eq!(CONTRADICTION_SUDOKU3.all_numbers_possible(), true);
+        assert!(VALID_SUDOKU.all_numbers_possible());
+        assert!(!CONTRADICTION_SUDOKU1.all_numbers_possible());
+        assert!(!CONTRADICTION_SUDOKU2.all_numbers_possible());
+        assert!(CONTRADICTION_SUDOKU3.all_numbers_possible());
     }
-}
 
-// TODO: convert to unit test
-fn solve_test1() {
-    let input = "
- 8 9  4
-5 6 8 79
-943 6  8
- 9    158
-6  528  4
-875    6
- 5  4 639
- 69 7 8 1
-  8  9 4 ";
-
-    let game_state = parse_game_state(&input).unwrap();
-    println!("{}", format_game_state(&game_state));
-    let mut solver_state = game_to_solver_state(&game_state);
-    let (_won, _lost) = compute_solve1(&mut solver_state, true);
-    let game_state = solver_to_game_state(&solver_state);
-    println!("{}", format_game_state(&game_state));
-}
-
-// TODO: convert to unit test
-fn parse_test1() {
-    let input = "
+    #[test]
+    fn test_parse_game_state() {
+        let input = "
 123456789
 234567891
 345678912
@@ -1008,6 +1734,50 @@ fn parse_test1() {
 891234567
 912345678
 ";
-    let game_state = parse_game_state(&input).unwrap();
-    println!("{}", format_game_state(&game_state));
+        let game_state = parse_game_state(input).unwrap();
+        assert_eq!(format_game_state(&game_state), input.trim_start());
+    }
+
+    #[test]
+    fn test_solve_game_state() {
+        let input = "
+ 8 9  4
+5 6 8 79
+943 6  8
+ 9    158
+6  528  4
+875    6
+ 5  4 639
+ 69 7 8 1
+  8  9 4 ";
+        let game_state = parse_game_state(input).unwrap();
+        let givens = game_state.iter().filter(|cell| cell.is_some()).count();
+        let mut solver_state = game_to_solver_state(&game_state);
+        let (won, lost, steps) = compute_solve1(&mut solver_state, &Singles);
+        assert!(won && !lost);
+        // one step for every cell that was solved
+        assert_eq!(steps.len(), 81 - givens);
+        let game_state = solver_to_game_state(&solver_state);
+        assert!(!format_game_state(&game_state).contains(' '));
+    }
+
+    #[test]
+    fn test_solve_tree_trace() {
+        let mut solver_state = Sudoku::from_str(crate::examples::EMPTY_SUDOKU).unwrap();
+        let (won, _, steps) =
+            compute_solve_tree(&mut solver_state, &Singles, CellSelection::FirstEmpty);
+        assert!(won);
+        // every cell is solved once on the way to the solution, the first one by a guess
+        assert_eq!(steps.len(), 81);
+        assert_eq!(
+            steps[0],
+            Placement {
+                row: 0,
+                col: 0,
+                number: NumberSet::N1,
+                guess: true
+            }
+        );
+        assert!(steps.iter().any(|step| !step.guess));
+    }
 }
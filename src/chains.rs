@@ -0,0 +1,509 @@
+//! Alternating inference chains: X-Chains, XY-Chains, AICs and discontinuous nice loops
+//!
+//! Two candidates are strongly linked if at least one of them is true: they are the only two
+//! candidates of a cell (bivalue cell), or the only two places for a number in a house
+//! (bilocal unit). They are weakly linked if at most one of them is true: they are in the same
+//! cell, or they are the same number in cells that see each other.
+//!
+//! An AIC starts and ends with a strong link and alternates between strong and weak links, so
+//! if its first candidate is false, its last one is true. Any candidate that is weakly linked
+//! to both ends can be removed.
+//! A discontinuous nice loop is a chain that returns to its first candidate. If it leaves and
+//! enters it with strong links, the candidate is true; if it leaves and enters it with weak
+//! links, the candidate is false.
+
+use std::collections::{HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
+
+use crate::base::{Candidate, Elimination, Ix, NumberSet, Sudoku};
+
+/// Chains with more candidates than this are not searched for
+pub const MAX_CHAIN_LENGTH: usize = 20;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LinkType {
+    Strong,
+    Weak,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ChainKind {
+    /// An AIC on a single number
+    XChain,
+    /// An AIC whose strong links are all bivalue cells
+    XYChain,
+    AIC,
+    DiscontinuousNiceLoop,
+}
+
+/// One candidate of a chain and the link to the next candidate, None for the last one
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ChainNode {
+    pub candidate: Candidate,
+    pub link: Option<LinkType>,
+}
+
+/// A chain and the candidates it eliminates
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chain {
+    pub kind: ChainKind,
+    pub nodes: Vec<ChainNode>,
+    pub eliminations: Vec<Elimination>,
+}
+
+impl Chain {
+    fn new(nodes: Vec<ChainNode>, eliminations: Vec<Elimination>) -> Self {
+        Self {
+            kind: classify(&nodes),
+            nodes,
+            eliminations,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn first(&self) -> Candidate {
+        self.nodes[0].candidate
+    }
+
+    pub fn last(&self) -> Candidate {
+        self.nodes[self.nodes.len() - 1].candidate
+    }
+}
+
+/// Eureka notation, e.g. "(5)r1c1=(5)r1c7-(3)r1c7=(3)r4c7"
+impl Display for Chain {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(formatter, "{}", format_chain_path(&self.nodes))
+    }
+}
+
+/// Formats a chain in Eureka notation: "=" is a strong link, "-" a weak one
+pub fn format_chain_path(nodes: &[ChainNode]) -> String {
+    let mut s = String::new();
+    for node in nodes {
+        s.push_str(&node.candidate.to_string());
+        match node.link {
+            Some(LinkType::Strong) => s.push('='),
+            Some(LinkType::Weak) => s.push('-'),
+            None => {}
+        }
+    }
+    s
+}
+
+/// Parses a chain in the format written by `format_chain_path`
+pub fn parse_chain_path(input: &str) -> Result<Vec<ChainNode>, String> {
+    let mut nodes: Vec<ChainNode> = Vec::new();
+    let mut rest = input.trim();
+    loop {
+        let end = rest.find(['=', '-']).unwrap_or(rest.len());
+        let candidate = rest[..end].parse()?;
+        let link = match rest[end..].chars().next() {
+            Some('=') => Some(LinkType::Strong),
+            Some('-') => Some(LinkType::Weak),
+            _ => None,
+        };
+        nodes.push(ChainNode { candidate, link });
+        if link.is_none() {
+            return Ok(nodes);
+        }
+        rest = &rest[end + 1..];
+    }
+}
+
+fn classify(nodes: &[ChainNode]) -> ChainKind {
+    let links = nodes
+        .windows(2)
+        .map(|w| (w[0].link, w[0].candidate, w[1].candidate));
+    if nodes.len() > 1 && nodes[0].candidate == nodes[nodes.len() - 1].candidate {
+        ChainKind::DiscontinuousNiceLoop
+    } else if nodes
+        .iter()
+        .all(|n| n.candidate.number == nodes[0].candidate.number)
+    {
+        ChainKind::XChain
+    } else if links.clone().all(|(link, a, b)| match link {
+        Some(LinkType::Strong) => a.cell() == b.cell(),
+        _ => a.cell() != b.cell(),
+    }) {
+        ChainKind::XYChain
+    } else {
+        ChainKind::AIC
+    }
+}
+
+/// Returns true if at most one of the candidates can be true
+pub fn weakly_linked(solver_state: &Sudoku<NumberSet>, a: Candidate, b: Candidate) -> bool {
+    a != b
+        && (a.cell() == b.cell() || (a.number == b.number && solver_state.sees(a.cell(), b.cell())))
+}
+
+fn key(c: Candidate) -> usize {
+    (usize::from(c.row) * 9 + usize::from(c.col)) * 9 + c.number.bits().trailing_zeros() as usize
+}
+
+/// The strong and weak links between the candidates of all unsolved cells
+pub struct LinkGraph {
+    candidates: Vec<Candidate>,
+    index: Vec<Option<usize>>,
+    strong: Vec<Vec<usize>>,
+    weak: Vec<Vec<usize>>,
+}
+
+impl LinkGraph {
+    pub fn new(solver_state: &Sudoku<NumberSet>) -> Self {
+        let candidates: Vec<Candidate> = solver_state
+            .iter_with_index()
+            .filter(|(_, _, cell)| cell.len() > 1)
+            .flat_map(|(r, c, cell)| cell.iter().map(move |n| Candidate::new(r, c, n)))
+            .collect();
+        let mut index = vec![None; 9 * 9 * 9];
+        for (i, &candidate) in candidates.iter().enumerate() {
+            index[key(candidate)] = Some(i);
+        }
+        let mut graph = Self {
+            strong: vec![Vec::new(); candidates.len()],
+            weak: vec![Vec::new(); candidates.len()],
+            candidates,
+            index,
+        };
+
+        for i in 0..graph.candidates.len() {
+            let a = graph.candidates[i];
+            let cell = *solver_state.get(a.row, a.col);
+            for n in cell.iter().filter(|&n| n != a.number) {
+                let j = graph.index_of(Candidate::new(a.row, a.col, n)).unwrap();
+                graph.weak[i].push(j);
+                if cell.len() == 2 {
+                    graph.strong[i].push(j);
+                }
+            }
+            for (r, c) in solver_state.peers(a.row, a.col) {
+                if let Some(j) = graph.index_of(Candidate::new(r, c, a.number)) {
+                    graph.weak[i].push(j);
+                }
+            }
+        }
+        for house in solver_state.houses() {
+            for number in NumberSet::all().iter() {
+                let cells: Vec<(Ix, Ix)> = house
+                    .cells()
+                    .iter()
+                    .cloned()
+                    .filter(|&(r, c)| solver_state.get(r, c).contains(number))
+                    .collect();
+                if let [(r1, c1), (r2, c2)] = cells[..] {
+                    let a = graph.index_of(Candidate::new(r1, c1, number));
+                    let b = graph.index_of(Candidate::new(r2, c2, number));
+                    if let (Some(a), Some(b)) = (a, b) {
+                        graph.strong[a].push(b);
+                        graph.strong[b].push(a);
+                    }
+                }
+            }
+        }
+        // two cells in a line and a block can be linked through both houses
+        for links in graph.strong.iter_mut() {
+            links.sort_unstable();
+            links.dedup();
+        }
+        graph
+    }
+
+    pub fn candidates(&self) -> &[Candidate] {
+        &self.candidates
+    }
+
+    pub fn index_of(&self, candidate: Candidate) -> Option<usize> {
+        self.index[key(candidate)]
+    }
+
+    /// Indices of the candidates that are strongly linked to the given one
+    pub fn strong_links(&self, i: usize) -> &[usize] {
+        &self.strong[i]
+    }
+
+    /// Indices of the candidates that are weakly linked to the given one. Strong links between
+    /// candidates of one cell or one number are weak links as well.
+    pub fn weak_links(&self, i: usize) -> &[usize] {
+        &self.weak[i]
+    }
+}
+
+/// Which links a search may use
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Links {
+    SingleNumber,
+    BivalueCells,
+    All,
+}
+
+impl Links {
+    fn allows(self, link: LinkType, a: Candidate, b: Candidate) -> bool {
+        match (self, link) {
+            (Links::All, _) => true,
+            (Links::SingleNumber, _) => a.number == b.number,
+            (Links::BivalueCells, LinkType::Strong) => a.cell() == b.cell(),
+            (Links::BivalueCells, LinkType::Weak) => a.cell() != b.cell(),
+        }
+    }
+}
+
+/// Breadth first search over alternating chains from one candidate, which is assumed true if
+/// `start_true` is set and false otherwise. State `2 * i + 1` means that candidate `i` is
+/// true, `2 * i` that it is false. Returns the parent of every reached state.
+fn search(graph: &LinkGraph, start: usize, start_true: bool, links: Links) -> Vec<Option<usize>> {
+    let start_state = 2 * start + start_true as usize;
+    let mut parent = vec![None; 2 * graph.candidates.len()];
+    let mut depth = vec![0; 2 * graph.candidates.len()];
+    parent[start_state] = Some(start_state);
+    let mut queue = VecDeque::new();
+    queue.push_back(start_state);
+    while let Some(state) = queue.pop_front() {
+        if depth[state] + 1 >= MAX_CHAIN_LENGTH {
+            continue;
+        }
+        let (i, is_true) = (state / 2, state % 2 == 1);
+        // a false candidate makes its strong links true, a true one its weak links false
+        let (next, link) = if is_true {
+            (graph.weak_links(i), LinkType::Weak)
+        } else {
+            (graph.strong_links(i), LinkType::Strong)
+        };
+        for &j in next {
+            let next_state = 2 * j + !is_true as usize;
+            if parent[next_state].is_none()
+                && links.allows(link, graph.candidates[i], graph.candidates[j])
+            {
+                parent[next_state] = Some(state);
+                depth[next_state] = depth[state] + 1;
+                queue.push_back(next_state);
+            }
+        }
+    }
+    parent
+}
+
+/// The chain that the search reached `state` by, or None if it uses a candidate twice
+fn path(graph: &LinkGraph, parent: &[Option<usize>], state: usize) -> Option<Vec<ChainNode>> {
+    let mut states = vec![state];
+    let mut current = state;
+    while let Some(p) = parent[current].filter(|&p| p != current) {
+        states.push(p);
+        current = p;
+    }
+    states.reverse();
+    let mut nodes: Vec<ChainNode> = states
+        .iter()
+        .map(|&s| ChainNode {
+            candidate: graph.candidates[s / 2],
+            link: Some(if s % 2 == 1 {
+                LinkType::Weak
+            } else {
+                LinkType::Strong
+            }),
+        })
+        .collect();
+    nodes.last_mut().unwrap().link = None;
+    let mut seen = HashSet::new();
+    if nodes.iter().all(|n| seen.insert(n.candidate)) {
+        Some(nodes)
+    } else {
+        None
+    }
+}
+
+/// Finds AICs with the given links that eliminate at least one candidate, shortest first
+fn find_aics_with(solver_state: &Sudoku<NumberSet>, links: Links) -> Vec<Chain> {
+    let graph = LinkGraph::new(solver_state);
+    let mut found = Vec::new();
+    let mut ends = HashSet::new();
+    for start in 0..graph.candidates.len() {
+        let parent = search(&graph, start, false, links);
+        for end in (0..graph.candidates.len()).filter(|&end| end != start) {
+            let state = 2 * end + 1;
+            if parent[state].is_none() || ends.contains(&(end, start)) {
+                continue;
+            }
+            let z = graph.candidates[end];
+            let eliminations: Vec<Elimination> = graph
+                .weak_links(start)
+                .iter()
+                .map(|&e| graph.candidates[e])
+                .filter(|&e| weakly_linked(solver_state, e, z))
+                .filter_map(|e| solver_state.elimination(e.row, e.col, e.number))
+                .collect();
+            if eliminations.is_empty() {
+                continue;
+            }
+            if let Some(nodes) = path(&graph, &parent, state) {
+                ends.insert((start, end));
+                found.push(Chain::new(nodes, eliminations));
+            }
+        }
+    }
+    found.sort_by_key(|chain| chain.len());
+    found
+}
+
+/// Finds X-Chains that eliminate at least one candidate, shortest first
+pub fn find_x_chains(solver_state: &Sudoku<NumberSet>) -> Vec<Chain> {
+    find_aics_with(solver_state, Links::SingleNumber)
+}
+
+/// Finds XY-Chains that eliminate at least one candidate, shortest first
+pub fn find_xy_chains(solver_state: &Sudoku<NumberSet>) -> Vec<Chain> {
+    find_aics_with(solver_state, Links::BivalueCells)
+}
+
+/// Finds AICs of any kind that eliminate at least one candidate, shortest first
+pub fn find_aics(solver_state: &Sudoku<NumberSet>) -> Vec<Chain> {
+    find_aics_with(solver_state, Links::All)
+}
+
+/// Finds discontinuous nice loops, shortest first. A loop with two strong links at its first
+/// candidate eliminates the other candidates of that cell, a loop with two weak links
+/// eliminates the candidate itself.
+pub fn find_nice_loops(solver_state: &Sudoku<NumberSet>) -> Vec<Chain> {
+    let graph = LinkGraph::new(solver_state);
+    let mut found = Vec::new();
+    for start in 0..graph.candidates.len() {
+        let a = graph.candidates[start];
+
+        // assuming a is false makes it true
+        let parent = search(&graph, start, false, Links::All);
+        if let Some(mut nodes) = parent[2 * start + 1].and_then(|p| path(&graph, &parent, p)) {
+            nodes.last_mut().unwrap().link = Some(LinkType::Strong);
+            nodes.push(ChainNode {
+                candidate: a,
+                link: None,
+            });
+            let others = *solver_state.get(a.row, a.col) - a.number;
+            let eliminations = solver_state.elimination(a.row, a.col, others);
+            found.push(Chain::new(nodes, eliminations.into_iter().collect()));
+            continue;
+        }
+
+        // assuming a is true makes a candidate true that is weakly linked to a
+        let parent = search(&graph, start, true, Links::All);
+        let end = (0..graph.candidates.len()).find(|&end| {
+            end != start
+                && parent[2 * end + 1].is_some()
+                && weakly_linked(solver_state, graph.candidates[end], a)
+        });
+        if let Some(mut nodes) = end.and_then(|end| path(&graph, &parent, 2 * end + 1)) {
+            nodes.last_mut().unwrap().link = Some(LinkType::Weak);
+            nodes.push(ChainNode {
+                candidate: a,
+                link: None,
+            });
+            let eliminations = solver_state.elimination(a.row, a.col, a.number);
+            found.push(Chain::new(nodes, eliminations.into_iter().collect()));
+        }
+    }
+    found.sort_by_key(|chain| chain.len());
+    found
+}
+
+type ChainFinder = fn(&Sudoku<NumberSet>) -> Vec<Chain>;
+
+/// compute field constraints: remove candidates using chains
+///
+/// Strategy:
+/// - X-Chains first, then XY-Chains, then any AIC, then discontinuous nice loops
+/// - all chains of the first kind that has any are applied and returned
+pub fn compute_chains(solver_state: &mut Sudoku<NumberSet>) -> Vec<Chain> {
+    let finders: [ChainFinder; 4] = [find_x_chains, find_xy_chains, find_aics, find_nice_loops];
+    for find in finders.iter() {
+        let found = find(solver_state);
+        if !found.is_empty() {
+            solver_state.apply_eliminations(found.iter().flat_map(|c| &c.eliminations));
+            return found;
+        }
+    }
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{Ix1, Ix2, Ix3, Ix5, Ix7, Ix8, Ix9};
+    use crate::examples::*;
+    use std::str::FromStr;
+
+    const N1: NumberSet = NumberSet::N1;
+    const N2: NumberSet = NumberSet::N2;
+    const N3: NumberSet = NumberSet::N3;
+
+    fn eliminated_cells(chain: &Chain) -> Vec<(Ix, Ix)> {
+        let mut cells: Vec<_> = chain.eliminations.iter().map(|e| (e.row, e.col)).collect();
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn test_x_chain() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        // strong links on 1 in row 1 and row 9, joined by a weak link in column 9
+        for c in Ix::all_indices().filter(|&c| c != Ix1 && c != Ix9) {
+            sudoku.eliminate(Ix1, c, N1);
+        }
+        for c in Ix::all_indices().filter(|&c| c != Ix2 && c != Ix9) {
+            sudoku.eliminate(Ix9, c, N1);
+        }
+        let found = find_x_chains(&sudoku);
+        let chain = found
+            .iter()
+            .find(|chain| chain.to_string() == "(1)r1c1=(1)r1c9-(1)r9c9=(1)r9c2")
+            .unwrap();
+        assert_eq!(chain.kind, ChainKind::XChain);
+        assert_eq!(
+            eliminated_cells(chain),
+            vec![(Ix2, Ix2), (Ix3, Ix2), (Ix7, Ix1), (Ix8, Ix1)]
+        );
+        compute_chains(&mut sudoku);
+        assert!(!sudoku.get(Ix2, Ix2).contains(N1));
+        assert!(sudoku.get(Ix5, Ix5).contains(N1));
+    }
+
+    #[test]
+    fn test_xy_chain() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        *sudoku.get_mut(Ix1, Ix1) = N1 | N2;
+        *sudoku.get_mut(Ix1, Ix5) = N2 | N3;
+        *sudoku.get_mut(Ix5, Ix5) = N3 | N1;
+        let found = find_xy_chains(&sudoku);
+        assert!(found.iter().all(|chain| chain.kind == ChainKind::XYChain));
+        let chain = found
+            .iter()
+            .find(|chain| chain.first() == Candidate::new(Ix1, Ix1, N1))
+            .unwrap();
+        assert_eq!(
+            chain.to_string(),
+            "(1)r1c1=(2)r1c1-(2)r1c5=(3)r1c5-(3)r5c5=(1)r5c5"
+        );
+        // r5c1 is the only cell with a 1 that sees both ends
+        assert_eq!(eliminated_cells(chain), vec![(Ix5, Ix1)]);
+    }
+
+    #[test]
+    fn test_parse_chain_path() {
+        let s = "(5)r1c1=(5)r1c7-(3)r1c7=(3)r4c7";
+        let nodes = parse_chain_path(s).unwrap();
+        assert_eq!(nodes.len(), 4);
+        assert_eq!(nodes[1].candidate, Candidate::new(Ix1, Ix7, NumberSet::N5));
+        assert_eq!(nodes[1].link, Some(LinkType::Weak));
+        assert_eq!(nodes[3].link, None);
+        assert_eq!(format_chain_path(&nodes), s);
+        assert!(parse_chain_path("(5)r1c1=").is_err());
+        assert!(parse_chain_path("(0)r1c1").is_err());
+        assert!(parse_chain_path("(5)r1c").is_err());
+    }
+}
@@ -0,0 +1,322 @@
+//! Single-number coloring: simple coloring (color trap and color wrap) and multi-coloring
+//!
+//! For one number, cells that are the only two places for it in a house (conjugate pairs)
+//! form clusters. Each cluster is colored with two colors so that conjugate cells differ;
+//! all cells of one color hold the number and none of the other.
+//!
+//! Color wrap: two cells of the same color see each other, so that color is false.
+//! Color trap: a cell outside the cluster sees both colors, so it can't hold the number.
+//! Multi-coloring: if a color of one cluster sees a color of another, at least one of the
+//! opposite colors is true. A cell seeing both opposite colors can't hold the number, and a
+//! color that sees both colors of another cluster is false.
+
+use crate::base::{Elimination, Ix, NumberSet, Sudoku};
+
+/// The cells of a cluster of conjugate pairs, split by color
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cluster {
+    pub number: NumberSet,
+    pub colors: [Vec<(Ix, Ix)>; 2],
+}
+
+impl Cluster {
+    /// The color of a cell, if it belongs to the cluster
+    pub fn color_of(&self, cell: (Ix, Ix)) -> Option<usize> {
+        (0..2).find(|&color| self.colors[color].contains(&cell))
+    }
+
+    pub fn contains(&self, cell: (Ix, Ix)) -> bool {
+        self.color_of(cell).is_some()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColoringKind {
+    ColorTrap,
+    ColorWrap,
+    MultiColoring,
+}
+
+/// A coloring deduction, the clusters it is based on and the candidates it eliminates
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Coloring {
+    pub kind: ColoringKind,
+    pub number: NumberSet,
+    /// One cluster for simple coloring, two for multi-coloring
+    pub clusters: Vec<Cluster>,
+    pub eliminations: Vec<Elimination>,
+}
+
+/// Finds the clusters of conjugate pairs of the number that contain at least one pair.
+/// Clusters that can't be colored consistently are left out.
+pub fn find_clusters(solver_state: &Sudoku<NumberSet>, number: NumberSet) -> Vec<Cluster> {
+    let mut pairs: Vec<[(Ix, Ix); 2]> = Vec::new();
+    for house in solver_state.houses() {
+        let cells: Vec<(Ix, Ix)> = house
+            .cells()
+            .iter()
+            .cloned()
+            .filter(|&(r, c)| solver_state.get(r, c).contains(number))
+            .collect();
+        if let [a, b] = cells[..] {
+            let unsolved = |(r, c): (Ix, Ix)| solver_state.get(r, c).len() > 1;
+            if unsolved(a) && unsolved(b) && !pairs.contains(&[a, b]) {
+                pairs.push([a, b]);
+            }
+        }
+    }
+
+    let mut clusters = Vec::new();
+    let mut done: Vec<(Ix, Ix)> = Vec::new();
+    for start in pairs.iter().map(|pair| pair[0]) {
+        if done.contains(&start) {
+            continue;
+        }
+        let mut colors = [vec![start], Vec::new()];
+        let mut queue = vec![(start, 0)];
+        let mut consistent = true;
+        while let Some((cell, color)) = queue.pop() {
+            for pair in pairs.iter().filter(|pair| pair.contains(&cell)) {
+                let other = if pair[0] == cell { pair[1] } else { pair[0] };
+                if colors[color].contains(&other) {
+                    consistent = false;
+                } else if !colors[1 - color].contains(&other) {
+                    colors[1 - color].push(other);
+                    queue.push((other, 1 - color));
+                }
+            }
+        }
+        done.extend(colors.iter().flatten());
+        if consistent {
+            for color in colors.iter_mut() {
+                color.sort();
+            }
+            clusters.push(Cluster { number, colors });
+        }
+    }
+    clusters
+}
+
+/// Returns true if the cell sees any of the cells
+fn sees_any(solver_state: &Sudoku<NumberSet>, cell: (Ix, Ix), cells: &[(Ix, Ix)]) -> bool {
+    cells.iter().any(|&other| solver_state.sees(cell, other))
+}
+
+/// Eliminations of the number in all cells of the color
+fn eliminate_color(
+    solver_state: &Sudoku<NumberSet>,
+    cells: &[(Ix, Ix)],
+    number: NumberSet,
+) -> Vec<Elimination> {
+    cells
+        .iter()
+        .filter_map(|&(r, c)| solver_state.elimination(r, c, number))
+        .collect()
+}
+
+/// Eliminations of the number in cells outside of the clusters that see both color groups
+fn eliminate_seeing_both(
+    solver_state: &Sudoku<NumberSet>,
+    clusters: &[&Cluster],
+    first: &[(Ix, Ix)],
+    second: &[(Ix, Ix)],
+    number: NumberSet,
+) -> Vec<Elimination> {
+    solver_state
+        .iter_with_index()
+        .filter(|&(r, c, _)| !clusters.iter().any(|cluster| cluster.contains((r, c))))
+        .filter(|&(r, c, _)| {
+            sees_any(solver_state, (r, c), first) && sees_any(solver_state, (r, c), second)
+        })
+        .filter_map(|(r, c, _)| solver_state.elimination(r, c, number))
+        .collect()
+}
+
+/// Finds color wraps and color traps
+pub fn find_simple_coloring(solver_state: &Sudoku<NumberSet>) -> Vec<Coloring> {
+    let mut found = Vec::new();
+    for number in NumberSet::all().iter() {
+        for cluster in find_clusters(solver_state, number) {
+            let wrapped = (0..2).find(|&color| {
+                let cells = &cluster.colors[color];
+                cells
+                    .iter()
+                    .any(|&cell| sees_any(solver_state, cell, cells))
+            });
+            let (kind, eliminations) = match wrapped {
+                Some(color) => (
+                    ColoringKind::ColorWrap,
+                    eliminate_color(solver_state, &cluster.colors[color], number),
+                ),
+                None => (
+                    ColoringKind::ColorTrap,
+                    eliminate_seeing_both(
+                        solver_state,
+                        &[&cluster],
+                        &cluster.colors[0],
+                        &cluster.colors[1],
+                        number,
+                    ),
+                ),
+            };
+            if !eliminations.is_empty() {
+                found.push(Coloring {
+                    kind,
+                    number,
+                    clusters: vec![cluster],
+                    eliminations,
+                });
+            }
+        }
+    }
+    found
+}
+
+/// Finds multi-coloring eliminations between pairs of clusters of the same number
+pub fn find_multi_coloring(solver_state: &Sudoku<NumberSet>) -> Vec<Coloring> {
+    let mut found = Vec::new();
+    for number in NumberSet::all().iter() {
+        let clusters = find_clusters(solver_state, number);
+        for (i, a) in clusters.iter().enumerate() {
+            for b in clusters.iter().skip(i + 1) {
+                let mut eliminations: Vec<Elimination> = Vec::new();
+                let sees = |x: &[(Ix, Ix)], y: &[(Ix, Ix)]| {
+                    x.iter().any(|&cell| sees_any(solver_state, cell, y))
+                };
+                for (x, y) in [(a, b), (b, a)].iter() {
+                    for color in 0..2 {
+                        // a color that sees both colors of the other cluster is false
+                        if sees(&x.colors[color], &y.colors[0])
+                            && sees(&x.colors[color], &y.colors[1])
+                        {
+                            eliminations.extend(eliminate_color(
+                                solver_state,
+                                &x.colors[color],
+                                number,
+                            ));
+                        }
+                    }
+                }
+                for ca in 0..2 {
+                    for cb in 0..2 {
+                        if sees(&a.colors[ca], &b.colors[cb]) {
+                            eliminations.extend(eliminate_seeing_both(
+                                solver_state,
+                                &[a, b],
+                                &a.colors[1 - ca],
+                                &b.colors[1 - cb],
+                                number,
+                            ));
+                        }
+                    }
+                }
+                eliminations.sort_by_key(|e| (e.row, e.col));
+                eliminations.dedup();
+                if !eliminations.is_empty() {
+                    found.push(Coloring {
+                        kind: ColoringKind::MultiColoring,
+                        number,
+                        clusters: vec![a.clone(), b.clone()],
+                        eliminations,
+                    });
+                }
+            }
+        }
+    }
+    found
+}
+
+/// compute field constraints: remove candidates using simple coloring
+pub fn compute_simple_coloring(solver_state: &mut Sudoku<NumberSet>) -> Vec<Coloring> {
+    let found = find_simple_coloring(solver_state);
+    solver_state.apply_eliminations(found.iter().flat_map(|c| &c.eliminations));
+    found
+}
+
+/// compute field constraints: remove candidates using multi-coloring
+pub fn compute_multi_coloring(solver_state: &mut Sudoku<NumberSet>) -> Vec<Coloring> {
+    let found = find_multi_coloring(solver_state);
+    solver_state.apply_eliminations(found.iter().flat_map(|c| &c.eliminations));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{Ix1, Ix2, Ix3, Ix5, Ix8};
+    use crate::examples::*;
+    use std::str::FromStr;
+
+    const N1: NumberSet = NumberSet::N1;
+
+    /// Removes 1 from every cell of the line except for the two given ones
+    fn conjugate(sudoku: &mut Sudoku<NumberSet>, a: (Ix, Ix), b: (Ix, Ix)) {
+        let cells: Vec<(Ix, Ix)> = if a.0 == b.0 {
+            Ix::all_indices().map(|c| (a.0, c)).collect()
+        } else {
+            Ix::all_indices().map(|r| (r, a.1)).collect()
+        };
+        for (r, c) in cells.into_iter().filter(|&cell| cell != a && cell != b) {
+            sudoku.eliminate(r, c, N1);
+        }
+    }
+
+    fn eliminated_cells(coloring: &Coloring) -> Vec<(Ix, Ix)> {
+        let mut cells: Vec<_> = coloring
+            .eliminations
+            .iter()
+            .map(|e| (e.row, e.col))
+            .collect();
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn test_color_trap() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        conjugate(&mut sudoku, (Ix1, Ix1), (Ix1, Ix5));
+        conjugate(&mut sudoku, (Ix1, Ix5), (Ix5, Ix5));
+        conjugate(&mut sudoku, (Ix5, Ix5), (Ix5, Ix1));
+        let found = find_simple_coloring(&sudoku);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, ColoringKind::ColorTrap);
+        assert_eq!(
+            found[0].clusters[0].colors,
+            [vec![(Ix1, Ix1), (Ix5, Ix5)], vec![(Ix1, Ix5), (Ix5, Ix1)]]
+        );
+        // the rest of column 1 sees both r1c1 and r5c1
+        assert_eq!(found[0].eliminations.len(), 7);
+        compute_simple_coloring(&mut sudoku);
+        assert!(!sudoku.get(Ix3, Ix1).contains(N1));
+    }
+
+    #[test]
+    fn test_color_wrap() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        conjugate(&mut sudoku, (Ix1, Ix1), (Ix1, Ix5));
+        conjugate(&mut sudoku, (Ix1, Ix5), (Ix5, Ix5));
+        conjugate(&mut sudoku, (Ix5, Ix5), (Ix5, Ix2));
+        conjugate(&mut sudoku, (Ix5, Ix2), (Ix2, Ix2));
+        let found = find_simple_coloring(&sudoku);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, ColoringKind::ColorWrap);
+        // r1c1 and r2c2 share block 1, so their color is false
+        assert_eq!(
+            eliminated_cells(&found[0]),
+            vec![(Ix1, Ix1), (Ix2, Ix2), (Ix5, Ix5)]
+        );
+    }
+
+    #[test]
+    fn test_multi_coloring() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        conjugate(&mut sudoku, (Ix1, Ix1), (Ix1, Ix5));
+        conjugate(&mut sudoku, (Ix2, Ix2), (Ix8, Ix2));
+        assert!(find_simple_coloring(&sudoku).is_empty());
+        let found = find_multi_coloring(&sudoku);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].clusters.len(), 2);
+        // r1c1 and r2c2 see each other, so r1c5 or r8c2 is 1
+        assert_eq!(eliminated_cells(&found[0]), vec![(Ix8, Ix5)]);
+    }
+}
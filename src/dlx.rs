@@ -0,0 +1,193 @@
+//! Exact cover solver: Knuth's Algorithm X implemented with Dancing Links
+//!
+//! The matrix is stored as a toroidal doubly-linked list in flat vectors. Node 0 is the root,
+//! nodes 1..=num_cols are the column headers and every 1 in the matrix is one further node.
+
+pub struct ExactCover {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    // column header of every node
+    col: Vec<usize>,
+    // row id of every node (unused for the root and the headers)
+    row: Vec<usize>,
+    // number of nodes per column, indexed by header node
+    size: Vec<usize>,
+}
+
+impl ExactCover {
+    pub fn new(num_cols: usize) -> Self {
+        let n = num_cols + 1;
+        Self {
+            left: (0..n).map(|i| (i + n - 1) % n).collect(),
+            right: (0..n).map(|i| (i + 1) % n).collect(),
+            up: (0..n).collect(),
+            down: (0..n).collect(),
+            col: (0..n).collect(),
+            row: vec![usize::MAX; n],
+            size: vec![0; n],
+        }
+    }
+
+    /// Adds a row with the given id that has a 1 in each of the given columns
+    pub fn add_row(&mut self, row_id: usize, cols: &[usize]) {
+        let first = self.col.len();
+        for (k, &c) in cols.iter().enumerate() {
+            let header = c + 1;
+            let node = first + k;
+            // insert at the bottom of the column
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            let last = self.up[header];
+            self.down[last] = node;
+            self.up[header] = node;
+            // insert at the end of the row
+            self.left.push(if k == 0 { node } else { node - 1 });
+            self.right.push(first);
+            if k > 0 {
+                self.right[node - 1] = node;
+                self.left[first] = node;
+            }
+            self.col.push(header);
+            self.row.push(row_id);
+            self.size[header] += 1;
+        }
+    }
+
+    fn cover(&mut self, header: usize) {
+        let (l, r) = (self.left[header], self.right[header]);
+        self.right[l] = r;
+        self.left[r] = l;
+        let mut i = self.down[header];
+        while i != header {
+            let mut j = self.right[i];
+            while j != i {
+                let (u, d) = (self.up[j], self.down[j]);
+                self.down[u] = d;
+                self.up[d] = u;
+                self.size[self.col[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    fn uncover(&mut self, header: usize) {
+        let mut i = self.up[header];
+        while i != header {
+            let mut j = self.left[i];
+            while j != i {
+                let (u, d) = (self.up[j], self.down[j]);
+                self.down[u] = j;
+                self.up[d] = j;
+                self.size[self.col[j]] += 1;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+        let (l, r) = (self.left[header], self.right[header]);
+        self.right[l] = header;
+        self.left[r] = header;
+    }
+
+    /// Enumerates exact covers, calling `on_solution` with the row ids of each one.
+    /// The search stops as soon as `on_solution` returns false.
+    pub fn search(&mut self, on_solution: &mut dyn FnMut(&[usize]) -> bool) {
+        let mut partial = Vec::new();
+        self.search_rec(&mut partial, on_solution);
+    }
+
+    /// Returns the row ids of the first exact cover found, if any
+    pub fn first_solution(&mut self) -> Option<Vec<usize>> {
+        let mut solution = None;
+        self.search(&mut |rows| {
+            solution = Some(rows.to_vec());
+            false
+        });
+        solution
+    }
+
+    // returns false if the search should stop
+    fn search_rec(
+        &mut self,
+        partial: &mut Vec<usize>,
+        on_solution: &mut dyn FnMut(&[usize]) -> bool,
+    ) -> bool {
+        if self.right[0] == 0 {
+            return on_solution(partial);
+        }
+        // branch on the column with the fewest remaining rows
+        let mut header = self.right[0];
+        let mut j = self.right[header];
+        while j != 0 {
+            if self.size[j] < self.size[header] {
+                header = j;
+            }
+            j = self.right[j];
+        }
+        if self.size[header] == 0 {
+            return true;
+        }
+
+        self.cover(header);
+        let mut keep_going = true;
+        let mut r = self.down[header];
+        while r != header {
+            partial.push(self.row[r]);
+            let mut j = self.right[r];
+            while j != r {
+                self.cover(self.col[j]);
+                j = self.right[j];
+            }
+            keep_going = self.search_rec(partial, on_solution);
+            let mut j = self.left[r];
+            while j != r {
+                self.uncover(self.col[j]);
+                j = self.left[j];
+            }
+            partial.pop();
+            if !keep_going {
+                break;
+            }
+            r = self.down[r];
+        }
+        self.uncover(header);
+        keep_going
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_knuth_example() {
+        // example from Knuth's "Dancing Links" paper, the unique cover is rows 0, 3 and 4
+        let mut ec = ExactCover::new(7);
+        ec.add_row(0, &[2, 4, 5]);
+        ec.add_row(1, &[0, 3, 6]);
+        ec.add_row(2, &[1, 2, 5]);
+        ec.add_row(3, &[0, 3]);
+        ec.add_row(4, &[1, 6]);
+        ec.add_row(5, &[3, 4, 6]);
+        let mut solutions = Vec::new();
+        ec.search(&mut |rows| {
+            let mut rows = rows.to_vec();
+            rows.sort();
+            solutions.push(rows);
+            true
+        });
+        assert_eq!(solutions, vec![vec![0, 3, 4]]);
+        // the matrix is restored after a search
+        assert_eq!(ec.first_solution().map(|s| s.len()), Some(3));
+    }
+
+    #[test]
+    fn test_no_cover() {
+        let mut ec = ExactCover::new(3);
+        ec.add_row(0, &[0, 1]);
+        ec.add_row(1, &[1, 2]);
+        assert_eq!(ec.first_solution(), None);
+    }
+}
@@ -1,4 +1,4 @@
-pub const SOLVED_SUDOKU: &'static str = "\
+pub const SOLVED_SUDOKU: &str = "\
 ┌─┬─┬─┬─┬─┬─┬─┬─┬─┐
 │7│8│2│9│5│3│4│1│6│
 ├─┼─┼─┼─┼─┼─┼─┼─┼─┤
@@ -19,7 +19,7 @@ pub const SOLVED_SUDOKU: &'static str = "\
 │3│2│8│6│1│9│5│4│7│
 └─┴─┴─┴─┴─┴─┴─┴─┴─┘";
 
-pub const SUDOKU1: &'static str = "\
+pub const SUDOKU1: &str = "\
 ┌─┬─┬─┬─┬─┬─┬─┬─┬─┐
 │ │8│ │9│ │ │4│ │ │
 ├─┼─┼─┼─┼─┼─┼─┼─┼─┤
@@ -40,7 +40,7 @@ pub const SUDOKU1: &'static str = "\
 │ │ │8│ │ │9│ │4│ │
 └─┴─┴─┴─┴─┴─┴─┴─┴─┘";
 
-pub const SUDOKU2: &'static str = "\
+pub const SUDOKU2: &str = "\
 ┌─┬─┬─┬─┬─┬─┬─┬─┬─┐
 │4│ │ │ │ │ │8│ │5│
 ├─┼─┼─┼─┼─┼─┼─┼─┼─┤
@@ -61,7 +61,7 @@ pub const SUDOKU2: &'static str = "\
 │1│ │4│ │ │ │ │ │ │
 └─┴─┴─┴─┴─┴─┴─┴─┴─┘";
 
-pub const SUDOKU3: &'static str = "\
+pub const SUDOKU3: &str = "\
 ┌─┬─┬─┬─┬─┬─┬─┬─┬─┐
 │8│ │ │ │ │ │ │ │ │
 ├─┼─┼─┼─┼─┼─┼─┼─┼─┤
@@ -83,7 +83,7 @@ pub const SUDOKU3: &'static str = "\
 └─┴─┴─┴─┴─┴─┴─┴─┴─┘";
 
 
-pub const EMPTY_SUDOKU: &'static str = "\
+pub const EMPTY_SUDOKU: &str = "\
 ┌─┬─┬─┬─┬─┬─┬─┬─┬─┐
 │ │ │ │ │ │ │ │ │ │
 ├─┼─┼─┼─┼─┼─┼─┼─┼─┤
@@ -103,3 +103,15 @@ pub const EMPTY_SUDOKU: &'static str = "\
 ├─┼─┼─┼─┼─┼─┼─┼─┼─┤
 │ │ │ │ │ │ │ │ │ │
 └─┴─┴─┴─┴─┴─┴─┴─┴─┘";
+
+/// Regions of a jigsaw sudoku, see `Layout::jigsaw`
+pub const JIGSAW_REGIONS: &str = "\
+AAABBBBBC
+AAAABBBCC
+DAAEEBCCC
+DDDEEECCC
+DDDEEFFFF
+GDDHEEFFF
+GGHHHIIFF
+GGGHHHIII
+GGGHHIIII";
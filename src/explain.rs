@@ -0,0 +1,171 @@
+//! Explanations of solving steps in plain text or Markdown
+//!
+//! Cells are written as r3c5, candidates as (7)r3c5 and chains in Eureka notation, e.g.
+//! "Naked Pair {2,7} in row 4 removes 2 from r4c1, r4c9".
+
+use std::collections::BTreeMap;
+
+use crate::base::{cell_name, Ix, NumberSet};
+use crate::chains::format_chain_path;
+use crate::strategy::Step;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+    PlainText,
+    Markdown,
+}
+
+/// A single number as "7", more than one as "{2,7}"
+pub fn format_numbers(numbers: NumberSet) -> String {
+    if numbers.len() == 1 {
+        return numbers.to_string();
+    }
+    let numbers: Vec<String> = numbers.iter().map(|n| n.to_string()).collect();
+    format!("{{{}}}", numbers.join(","))
+}
+
+/// "a", "a and b", "a, b and c"
+fn join_and(items: &[String]) -> String {
+    match items.split_last() {
+        None => String::new(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+fn join_cells(cells: &[(Ix, Ix)]) -> String {
+    let names: Vec<String> = cells.iter().map(|&cell| cell_name(cell)).collect();
+    names.join(", ")
+}
+
+/// The eliminations grouped by number, e.g. "removes 2 from r4c1, r4c9 and 7 from r4c3"
+fn format_removals(step: &Step) -> String {
+    let mut by_number: BTreeMap<NumberSet, Vec<(Ix, Ix)>> = BTreeMap::new();
+    for candidate in step.eliminated_candidates() {
+        by_number
+            .entry(candidate.number)
+            .or_default()
+            .push(candidate.cell());
+    }
+    let groups: Vec<String> = by_number
+        .iter()
+        .map(|(number, cells)| format!("{} from {}", number, join_cells(cells)))
+        .collect();
+    format!("removes {}", join_and(&groups))
+}
+
+/// Explains one step in a sentence
+pub fn explain_step(step: &Step, format: Format) -> String {
+    let mut sentence = match format {
+        Format::PlainText => step.technique.clone(),
+        Format::Markdown => format!("**{}**", step.technique),
+    };
+    if !step.placements.is_empty() {
+        // only a hidden single has a house worth mentioning
+        if let [house] = step.houses[..] {
+            sentence += &format!(" in {}", house);
+        }
+        let placements: Vec<String> = step
+            .placements
+            .iter()
+            .map(|p| format!("{} = {}", cell_name(p.cell()), p.number))
+            .collect();
+        return format!("{}: {}", sentence, join_and(&placements));
+    }
+    if !step.numbers.is_empty() {
+        sentence += &format!(" {}", format_numbers(step.numbers));
+    }
+    if !step.chain.is_empty() {
+        let chain = format_chain_path(&step.chain);
+        sentence += &match format {
+            Format::PlainText => format!(" {}", chain),
+            Format::Markdown => format!(" `{}`", chain),
+        };
+    } else if !step.houses.is_empty() {
+        let houses: Vec<String> = step.houses.iter().map(|h| h.to_string()).collect();
+        sentence += &format!(" in {}", join_and(&houses));
+    } else if !step.cells.is_empty() {
+        sentence += &format!(" on {}", join_cells(&step.cells));
+    }
+    if !step.eliminations.is_empty() {
+        sentence += &format!(" {}", format_removals(step));
+    }
+    sentence
+}
+
+/// Explains a solve path, one numbered line per step
+pub fn explain(steps: &[Step], format: Format) -> String {
+    steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| format!("{}. {}\n", i + 1, explain_step(step, format)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{Candidate, Elimination, House, Ix1, Ix2, Ix3, Ix4, Ix5, Ix9};
+    use crate::chains::parse_chain_path;
+
+    const N2: NumberSet = NumberSet::N2;
+    const N7: NumberSet = NumberSet::N7;
+
+    fn naked_pair() -> Step {
+        let mut step = Step::new(
+            "Naked Pair",
+            vec![House::row(Ix4)],
+            vec![(Ix4, Ix3), (Ix4, Ix5)],
+            vec![
+                Elimination {
+                    row: Ix4,
+                    col: Ix1,
+                    numbers: N2,
+                },
+                Elimination {
+                    row: Ix4,
+                    col: Ix9,
+                    numbers: N2 | N7,
+                },
+            ],
+        );
+        step.numbers = N2 | N7;
+        step
+    }
+
+    #[test]
+    fn test_explain_step() {
+        assert_eq!(
+            explain_step(&naked_pair(), Format::PlainText),
+            "Naked Pair {2,7} in row 4 removes 2 from r4c1, r4c9 and 7 from r4c9"
+        );
+        let guess = Step::guess(Candidate::new(Ix3, Ix5, N7));
+        assert_eq!(explain_step(&guess, Format::PlainText), "Guess: r3c5 = 7");
+
+        let mut chain = Step::new(
+            "X-Chain",
+            Vec::new(),
+            vec![(Ix1, Ix1), (Ix1, Ix5)],
+            vec![Elimination {
+                row: Ix2,
+                col: Ix1,
+                numbers: N2,
+            }],
+        );
+        chain.chain = parse_chain_path("(2)r1c1=(2)r1c5-(2)r2c5=(2)r2c9").unwrap();
+        assert_eq!(
+            explain_step(&chain, Format::Markdown),
+            "**X-Chain** `(2)r1c1=(2)r1c5-(2)r2c5=(2)r2c9` removes 2 from r2c1"
+        );
+    }
+
+    #[test]
+    fn test_explain() {
+        let steps = vec![Step::guess(Candidate::new(Ix1, Ix1, N2)), naked_pair()];
+        let text = explain(&steps, Format::Markdown);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "1. **Guess**: r1c1 = 2");
+        assert!(lines[1].starts_with("2. **Naked Pair** {2,7} in row 4 removes"));
+    }
+}
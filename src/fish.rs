@@ -0,0 +1,343 @@
+//! Fish: X-Wing (size 2), Swordfish (size 3) and Jellyfish (size 4)
+//!
+//! If a number can only go into n columns within n rows (the base), each of these columns
+//! (the cover) gets its number in one of the base rows. So the number can be removed from the
+//! rest of the cover columns. The same works with rows and columns swapped.
+//!
+//! A finned fish additionally has some candidates (the fins) outside of the cover, all within
+//! one block. Either one of the fins is true, or the fish holds, so only cover cells in the
+//! block of the fins can be eliminated. If removing the fins would leave a base line with
+//! less than two candidates, the fish is called sashimi.
+
+use crate::base::{combinations, Elimination, House, Ix, NumberSet, RegionType, Sudoku};
+
+/// Sizes of the fish that are searched for, from X-Wing to Jellyfish
+pub const FISH_SIZES: [usize; 3] = [2, 3, 4];
+
+/// A fish for one number and the candidates it eliminates
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fish {
+    pub number: NumberSet,
+    /// The lines in which the number is confined to the cover lines (apart from the fins)
+    pub base: Vec<House>,
+    /// The lines from which the number is removed outside of the base lines
+    pub cover: Vec<House>,
+    /// Empty for a basic fish
+    pub fins: Vec<(Ix, Ix)>,
+    pub sashimi: bool,
+    pub eliminations: Vec<Elimination>,
+}
+
+impl Fish {
+    pub fn size(&self) -> usize {
+        self.base.len()
+    }
+
+    pub fn is_finned(&self) -> bool {
+        !self.fins.is_empty()
+    }
+
+    /// Name of the pattern, e.g. "X-Wing" or "Finned Swordfish"
+    pub fn name(&self) -> String {
+        let fish = match self.size() {
+            2 => "X-Wing",
+            3 => "Swordfish",
+            4 => "Jellyfish",
+            _ => "Fish",
+        };
+        match (self.is_finned(), self.sashimi) {
+            (false, _) => fish.to_string(),
+            (true, false) => format!("Finned {}", fish),
+            (true, true) => format!("Sashimi {}", fish),
+        }
+    }
+}
+
+/// Index of the cover line through a cell
+fn cover_index(base_kind: RegionType, (r, c): (Ix, Ix)) -> Ix {
+    match base_kind {
+        RegionType::Row => c,
+        _ => r,
+    }
+}
+
+/// Finds all fish of the given size with rows or columns as base lines that eliminate at
+/// least one candidate. Finned and sashimi fish are only searched for if `finned` is set.
+pub fn find_fish(solver_state: &Sudoku<NumberSet>, size: usize, finned: bool) -> Vec<Fish> {
+    let mut found = Vec::new();
+    for number in NumberSet::all().iter() {
+        for &(base_kind, cover_kind) in [
+            (RegionType::Row, RegionType::Col),
+            (RegionType::Col, RegionType::Row),
+        ]
+        .iter()
+        {
+            find_fish_for(
+                solver_state,
+                number,
+                size,
+                finned,
+                base_kind,
+                cover_kind,
+                &mut found,
+            );
+        }
+    }
+    found
+}
+
+fn find_fish_for(
+    solver_state: &Sudoku<NumberSet>,
+    number: NumberSet,
+    size: usize,
+    finned: bool,
+    base_kind: RegionType,
+    cover_kind: RegionType,
+    found: &mut Vec<Fish>,
+) {
+    // candidate positions of the number in each line where it isn't placed yet
+    let lines: Vec<(House, Vec<(Ix, Ix)>)> = Ix::all_indices()
+        .map(|ix| House::new(base_kind, ix))
+        .filter(|&house| !solver_state.house(house).any(|cell| *cell == number))
+        .map(|house| {
+            let positions = house
+                .cells()
+                .iter()
+                .cloned()
+                .filter(|&(r, c)| solver_state.get(r, c).contains(number))
+                .collect();
+            (house, positions)
+        })
+        .filter(|(_, positions): &(House, Vec<_>)| {
+            positions.len() >= 2 && (finned || positions.len() <= size)
+        })
+        .collect();
+    // the most cover lines a block spans, which bounds the lines the fins can add
+    let block_span = solver_state
+        .houses()
+        .filter(|house| house.kind() == RegionType::Block)
+        .map(|block| {
+            let mut ixs: Vec<Ix> = block
+                .cells()
+                .iter()
+                .map(|&p| cover_index(base_kind, p))
+                .collect();
+            ixs.sort();
+            ixs.dedup();
+            ixs.len()
+        })
+        .max()
+        .unwrap_or(0);
+
+    for base in combinations(&lines, size) {
+        let mut used: Vec<Ix> = base
+            .iter()
+            .flat_map(|(_, positions)| positions.iter().map(|&p| cover_index(base_kind, p)))
+            .collect();
+        used.sort();
+        used.dedup();
+        let base_houses: Vec<House> = base.iter().map(|(house, _)| *house).collect();
+        let in_base = |r: Ix, c: Ix| base_houses.iter().any(|h| h.contains(r, c));
+
+        if used.len() == size {
+            let cover: Vec<House> = used.iter().map(|&ix| House::new(cover_kind, ix)).collect();
+            let eliminations: Vec<Elimination> = cover
+                .iter()
+                .flat_map(|h| h.cells().iter().cloned())
+                .filter(|&(r, c)| !in_base(r, c))
+                .filter_map(|(r, c)| solver_state.elimination(r, c, number))
+                .collect();
+            if !eliminations.is_empty() {
+                found.push(Fish {
+                    number,
+                    base: base_houses.clone(),
+                    cover,
+                    fins: Vec::new(),
+                    sashimi: false,
+                    eliminations,
+                });
+            }
+            continue;
+        }
+        // fins within one block add at most the lines the block spans
+        if !finned || used.len() > size + block_span {
+            continue;
+        }
+
+        for cover_ixs in combinations(&used, size) {
+            let fins: Vec<(Ix, Ix)> = base
+                .iter()
+                .flat_map(|(_, positions)| positions.iter().cloned())
+                .filter(|&p| !cover_ixs.contains(&cover_index(base_kind, p)))
+                .collect();
+            let fin_block = match solver_state
+                .houses_for_cell(fins[0].0, fins[0].1)
+                .find(|h| h.kind() == RegionType::Block)
+            {
+                Some(block) => block,
+                None => continue,
+            };
+            if !fins.iter().all(|&(r, c)| fin_block.contains(r, c)) {
+                continue;
+            }
+            let cover: Vec<House> = cover_ixs
+                .iter()
+                .map(|&ix| House::new(cover_kind, ix))
+                .collect();
+            let eliminations: Vec<Elimination> = cover
+                .iter()
+                .flat_map(|h| h.cells().iter().cloned())
+                .filter(|&(r, c)| !in_base(r, c) && fin_block.contains(r, c))
+                .filter_map(|(r, c)| solver_state.elimination(r, c, number))
+                .collect();
+            if eliminations.is_empty() {
+                continue;
+            }
+            let sashimi = base.iter().any(|(_, positions)| {
+                positions
+                    .iter()
+                    .filter(|&&p| cover_ixs.contains(&cover_index(base_kind, p)))
+                    .count()
+                    < 2
+            });
+            found.push(Fish {
+                number,
+                base: base_houses.clone(),
+                cover,
+                fins,
+                sashimi,
+                eliminations,
+            });
+        }
+    }
+}
+
+/// compute field constraints: remove candidates using basic fish
+///
+/// X-Wings are tried first, then Swordfish, then Jellyfish. All fish of the smallest size that
+/// has any are applied and returned.
+pub fn compute_fish(solver_state: &mut Sudoku<NumberSet>) -> Vec<Fish> {
+    compute_fish_impl(solver_state, false)
+}
+
+/// compute field constraints: remove candidates using finned and sashimi fish
+///
+/// Like `compute_fish`, but only returns fish with fins. The standard pipeline has them as
+/// Finned X-Wing, Swordfish and Jellyfish, each a bit harder than the basic fish.
+pub fn compute_finned_fish(solver_state: &mut Sudoku<NumberSet>) -> Vec<Fish> {
+    compute_fish_impl(solver_state, true)
+}
+
+fn compute_fish_impl(solver_state: &mut Sudoku<NumberSet>, finned: bool) -> Vec<Fish> {
+    for &size in FISH_SIZES.iter() {
+        let found: Vec<Fish> = find_fish(solver_state, size, finned)
+            .into_iter()
+            .filter(|fish| fish.is_finned() == finned)
+            .collect();
+        if !found.is_empty() {
+            solver_state.apply_eliminations(found.iter().flat_map(|f| &f.eliminations));
+            return found;
+        }
+    }
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{Ix1, Ix2, Ix3, Ix4, Ix5, Ix6, Ix7, Ix8, Ix9, Layout};
+    use crate::examples::*;
+    use std::str::FromStr;
+
+    /// Removes the number from every cell of the row except for the given columns
+    fn restrict_row(sudoku: &mut Sudoku<NumberSet>, r: Ix, number: NumberSet, cols: &[Ix]) {
+        for c in Ix::all_indices().filter(|c| !cols.contains(c)) {
+            sudoku.eliminate(r, c, number);
+        }
+    }
+
+    #[test]
+    fn test_x_wing() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        restrict_row(&mut sudoku, Ix2, NumberSet::N4, &[Ix2, Ix8]);
+        restrict_row(&mut sudoku, Ix5, NumberSet::N4, &[Ix2, Ix8]);
+        let found = compute_fish(&mut sudoku);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name(), "X-Wing");
+        assert_eq!(found[0].base, vec![House::row(Ix2), House::row(Ix5)]);
+        assert_eq!(found[0].cover, vec![House::col(Ix2), House::col(Ix8)]);
+        // the other seven rows lose the 4 in both cover columns
+        assert_eq!(found[0].eliminations.len(), 14);
+        assert!(!sudoku.get(Ix9, Ix8).contains(NumberSet::N4));
+        assert!(sudoku.get(Ix9, Ix9).contains(NumberSet::N4));
+    }
+
+    #[test]
+    fn test_swordfish() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        restrict_row(&mut sudoku, Ix1, NumberSet::N7, &[Ix1, Ix5]);
+        restrict_row(&mut sudoku, Ix5, NumberSet::N7, &[Ix5, Ix9]);
+        restrict_row(&mut sudoku, Ix9, NumberSet::N7, &[Ix1, Ix9]);
+        assert!(find_fish(&sudoku, 2, false).is_empty());
+        let found = compute_fish(&mut sudoku);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name(), "Swordfish");
+        assert_eq!(found[0].eliminations.len(), 18);
+    }
+
+    #[test]
+    fn test_finned_x_wing() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        restrict_row(&mut sudoku, Ix2, NumberSet::N4, &[Ix2, Ix8]);
+        // the fin in r5c9 shares block 6 with the cover cells r4c8 and r6c8
+        restrict_row(&mut sudoku, Ix5, NumberSet::N4, &[Ix2, Ix8, Ix9]);
+        assert!(compute_fish(&mut sudoku).is_empty());
+        let found = compute_finned_fish(&mut sudoku);
+        let fish = found
+            .iter()
+            .find(|f| f.cover == vec![House::col(Ix2), House::col(Ix8)])
+            .unwrap();
+        assert_eq!(fish.name(), "Finned X-Wing");
+        assert_eq!(fish.fins, vec![(Ix5, Ix9)]);
+        let mut eliminated: Vec<_> = fish.eliminations.iter().map(|e| (e.row, e.col)).collect();
+        eliminated.sort();
+        assert_eq!(eliminated, vec![(Ix4, Ix8), (Ix6, Ix8)]);
+        assert!(!sudoku.get(Ix6, Ix8).contains(NumberSet::N4));
+        assert!(sudoku.get(Ix7, Ix8).contains(NumberSet::N4));
+    }
+
+    #[test]
+    fn test_finned_x_wing_jigsaw() {
+        let layout = Layout::jigsaw(9, JIGSAW_REGIONS).unwrap();
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU)
+            .unwrap()
+            .with_layout(layout)
+            .unwrap();
+        // the fins r1c4, r1c6, r1c7 and r1c8 span four columns of the second region
+        restrict_row(
+            &mut sudoku,
+            Ix1,
+            NumberSet::N4,
+            &[Ix1, Ix4, Ix5, Ix6, Ix7, Ix8],
+        );
+        restrict_row(&mut sudoku, Ix6, NumberSet::N4, &[Ix1, Ix5]);
+        let found = find_fish(&sudoku, 2, true);
+        let fish = found
+            .iter()
+            .find(|f| f.cover == vec![House::col(Ix1), House::col(Ix5)])
+            .unwrap();
+        assert_eq!(
+            fish.fins,
+            vec![(Ix1, Ix4), (Ix1, Ix6), (Ix1, Ix7), (Ix1, Ix8)]
+        );
+        assert_eq!(
+            fish.eliminations,
+            vec![Elimination {
+                row: Ix2,
+                col: Ix5,
+                numbers: NumberSet::N4
+            }]
+        );
+        assert!(sudoku.get(Ix3, Ix5).contains(NumberSet::N4));
+    }
+}
@@ -0,0 +1,353 @@
+//! Forcing chains and nishio: the last logical resort before guessing
+//!
+//! A candidate is assumed to be true and singles are propagated until nothing changes.
+//!
+//! Nishio: if the assumption leads to a contradiction, the candidate is false.
+//! Cell forcing chain: one of the candidates of a cell is true, so whatever follows from all of
+//! them that don't lead to a contradiction is true.
+//! Unit forcing chain: the same for the places of a number in a house.
+//!
+//! Every solution lies in one of the branches, so this is also sound for grids with more than
+//! one solution.
+//!
+//! They are part of `strategy::Pipeline::standard`, so `compute_solve_tree` with that pipeline
+//! tries them before every guess.
+
+use std::collections::HashMap;
+
+use crate::base::{
+    compute_exclude, compute_take_cell, Board, Candidate, Elimination, House, NumberSet, Sudoku,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ForcingKind {
+    Nishio,
+    Cell,
+    Unit,
+}
+
+/// What follows from assuming that a candidate is true
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Branch {
+    pub assumption: Candidate,
+    /// The cells that get solved by singles after the assumption, in row-major order
+    pub placements: Vec<Candidate>,
+    pub contradiction: bool,
+}
+
+/// A forcing chain or nishio and the candidates it eliminates
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForcingChain {
+    pub kind: ForcingKind,
+    /// The house of a unit forcing chain
+    pub house: Option<House>,
+    /// One branch per assumption, for nishio only the one that leads to a contradiction
+    pub branches: Vec<Branch>,
+    pub eliminations: Vec<Elimination>,
+}
+
+/// Assumes that the candidate is true and propagates singles
+pub fn assume(
+    solver_state: &Sudoku<NumberSet>,
+    assumption: Candidate,
+) -> (Sudoku<NumberSet>, Branch) {
+    let mut state = *solver_state;
+    *state.get_mut(assumption.row, assumption.col) = assumption.number;
+    let contradiction = loop {
+        let old_state = state;
+        compute_exclude(&mut state);
+        compute_take_cell(&mut state);
+        if !state.has_no_contradiction() {
+            break true;
+        }
+        if old_state == state {
+            break false;
+        }
+    };
+    let placements = state
+        .iter_with_index()
+        .filter(|&(r, c, cell)| {
+            cell.is_singleton() && *solver_state.get(r, c) != *cell && (r, c) != assumption.cell()
+        })
+        .map(|(r, c, &cell)| Candidate::new(r, c, cell))
+        .collect();
+    let branch = Branch {
+        assumption,
+        placements,
+        contradiction,
+    };
+    (state, branch)
+}
+
+/// The results of assuming each candidate of the unsolved cells
+struct Assumptions<'a> {
+    solver_state: &'a Sudoku<NumberSet>,
+    results: HashMap<Candidate, (Sudoku<NumberSet>, Branch)>,
+}
+
+impl<'a> Assumptions<'a> {
+    fn new(solver_state: &'a Sudoku<NumberSet>) -> Self {
+        Self {
+            solver_state,
+            results: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, candidate: Candidate) -> &(Sudoku<NumberSet>, Branch) {
+        let solver_state = self.solver_state;
+        self.results
+            .entry(candidate)
+            .or_insert_with(|| assume(solver_state, candidate))
+    }
+
+    /// The forcing chain over the given assumptions, one of which has to be true, if all
+    /// branches that don't lead to a contradiction agree on eliminating anything
+    fn common_consequences(
+        &mut self,
+        kind: ForcingKind,
+        house: Option<House>,
+        assumptions: &[Candidate],
+    ) -> Option<ForcingChain> {
+        let mut possible: Option<Sudoku<NumberSet>> = None;
+        let mut branches = Vec::new();
+        for &assumption in assumptions {
+            let (state, branch) = self.get(assumption);
+            if !branch.contradiction {
+                possible = Some(match possible {
+                    None => *state,
+                    Some(mut union) => {
+                        union
+                            .iter_mut()
+                            .zip(state.iter())
+                            .for_each(|(u, &s)| *u |= s);
+                        union
+                    }
+                });
+            }
+            branches.push(branch.clone());
+        }
+        let possible = possible?;
+        let eliminations: Vec<Elimination> = self
+            .solver_state
+            .iter_with_index()
+            .filter_map(|(r, c, _)| self.solver_state.elimination(r, c, !*possible.get(r, c)))
+            .collect();
+        if eliminations.is_empty() {
+            return None;
+        }
+        Some(ForcingChain {
+            kind,
+            house,
+            branches,
+            eliminations,
+        })
+    }
+}
+
+fn unsolved_candidates(solver_state: &Sudoku<NumberSet>) -> Vec<Candidate> {
+    solver_state
+        .iter_with_index()
+        .filter(|(_, _, cell)| cell.len() > 1)
+        .flat_map(|(r, c, cell)| cell.iter().map(move |n| Candidate::new(r, c, n)))
+        .collect()
+}
+
+fn find_nishio_with(assumptions: &mut Assumptions) -> Vec<ForcingChain> {
+    let solver_state = assumptions.solver_state;
+    let mut found = Vec::new();
+    for candidate in unsolved_candidates(solver_state) {
+        let (_, branch) = assumptions.get(candidate);
+        if branch.contradiction {
+            found.push(ForcingChain {
+                kind: ForcingKind::Nishio,
+                house: None,
+                branches: vec![branch.clone()],
+                eliminations: solver_state
+                    .elimination(candidate.row, candidate.col, candidate.number)
+                    .into_iter()
+                    .collect(),
+            });
+        }
+    }
+    found
+}
+
+fn find_cell_forcing_with(assumptions: &mut Assumptions) -> Vec<ForcingChain> {
+    let solver_state = assumptions.solver_state;
+    solver_state
+        .iter_with_index()
+        .filter(|(_, _, cell)| cell.len() > 1)
+        .filter_map(|(r, c, cell)| {
+            let branches: Vec<Candidate> = cell.iter().map(|n| Candidate::new(r, c, n)).collect();
+            assumptions.common_consequences(ForcingKind::Cell, None, &branches)
+        })
+        .collect()
+}
+
+fn find_unit_forcing_with(assumptions: &mut Assumptions) -> Vec<ForcingChain> {
+    let solver_state = assumptions.solver_state;
+    let mut found = Vec::new();
+    for house in solver_state.houses() {
+        for number in NumberSet::all().iter() {
+            let places: Vec<Candidate> = house
+                .cells()
+                .iter()
+                .filter(|&&(r, c)| {
+                    let cell = *solver_state.get(r, c);
+                    cell.len() > 1 && cell.contains(number)
+                })
+                .map(|&(r, c)| Candidate::new(r, c, number))
+                .collect();
+            let placed = house
+                .cells()
+                .iter()
+                .any(|&(r, c)| *solver_state.get(r, c) == number);
+            if placed || places.len() < 2 {
+                continue;
+            }
+            found.extend(assumptions.common_consequences(ForcingKind::Unit, Some(house), &places));
+        }
+    }
+    found
+}
+
+/// Finds candidates that lead to a contradiction when assumed
+pub fn find_nishio(solver_state: &Sudoku<NumberSet>) -> Vec<ForcingChain> {
+    find_nishio_with(&mut Assumptions::new(solver_state))
+}
+
+/// Finds cell forcing chains
+pub fn find_cell_forcing_chains(solver_state: &Sudoku<NumberSet>) -> Vec<ForcingChain> {
+    find_cell_forcing_with(&mut Assumptions::new(solver_state))
+}
+
+/// Finds unit forcing chains
+pub fn find_unit_forcing_chains(solver_state: &Sudoku<NumberSet>) -> Vec<ForcingChain> {
+    find_unit_forcing_with(&mut Assumptions::new(solver_state))
+}
+
+/// compute field constraints: remove candidates using forcing chains
+///
+/// Strategy:
+/// - nishio first, then cell forcing chains, then unit forcing chains
+/// - the propagation of each assumption is shared between them
+/// - all results of the first kind that has any are applied and returned
+pub fn compute_forcing_chains(solver_state: &mut Sudoku<NumberSet>) -> Vec<ForcingChain> {
+    let found = {
+        let mut assumptions = Assumptions::new(solver_state);
+        let finders: [fn(&mut Assumptions) -> Vec<ForcingChain>; 3] = [
+            find_nishio_with,
+            find_cell_forcing_with,
+            find_unit_forcing_with,
+        ];
+        finders
+            .iter()
+            .map(|find| find(&mut assumptions))
+            .find(|found| !found.is_empty())
+            .unwrap_or_default()
+    };
+    solver_state.apply_eliminations(found.iter().flat_map(|f| &f.eliminations));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{compute_solve1, compute_solve_tree, CellSelection};
+    use crate::base::{Ix1, Ix2, Ix3, Ix5};
+    use crate::examples::*;
+    use crate::strategy::{Pipeline, SolveOptions, StrategyKind};
+    use std::str::FromStr;
+
+    const N1: NumberSet = NumberSet::N1;
+    const N2: NumberSet = NumberSet::N2;
+    const N3: NumberSet = NumberSet::N3;
+
+    #[test]
+    fn test_nishio() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        *sudoku.get_mut(Ix1, Ix1) = N1 | N2;
+        *sudoku.get_mut(Ix1, Ix2) = N1 | N3;
+        *sudoku.get_mut(Ix1, Ix3) = N1 | N3;
+        // a 1 in r1c1 leaves 3 for both r1c2 and r1c3
+        let (_, branch) = assume(&sudoku, Candidate::new(Ix1, Ix1, N1));
+        assert!(branch.contradiction);
+        let found = find_nishio(&sudoku);
+        assert!(found
+            .iter()
+            .all(|chain| chain.kind == ForcingKind::Nishio && chain.branches.len() == 1));
+        assert!(found
+            .iter()
+            .any(|chain| chain.branches[0].assumption == Candidate::new(Ix1, Ix1, N1)));
+        compute_forcing_chains(&mut sudoku);
+        assert_eq!(*sudoku.get(Ix1, Ix1), N2);
+    }
+
+    #[test]
+    fn test_cell_forcing_chain() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        *sudoku.get_mut(Ix1, Ix1) = N1 | N2;
+        *sudoku.get_mut(Ix1, Ix5) = N1 | N3;
+        *sudoku.get_mut(Ix5, Ix1) = N2 | N3;
+        let found = find_cell_forcing_chains(&sudoku);
+        let chain = found
+            .iter()
+            .find(|chain| chain.branches[0].assumption.cell() == (Ix1, Ix1))
+            .unwrap();
+        assert_eq!(chain.branches.len(), 2);
+        assert_eq!(
+            chain.branches[0].placements,
+            vec![Candidate::new(Ix1, Ix5, N3)]
+        );
+        assert_eq!(
+            chain.branches[1].placements,
+            vec![Candidate::new(Ix5, Ix1, N3)]
+        );
+        // either way, r5c5 sees a 3
+        assert!(chain.eliminations.contains(&Elimination {
+            row: Ix5,
+            col: Ix5,
+            numbers: N3
+        }));
+    }
+
+    #[test]
+    fn test_forcing_keeps_solution() {
+        // stuck for all pattern techniques
+        let line =
+            "8....51..1..6.83.9.7..3..5..5.......3..9.....4...5.6.1.1..6......3.1.47...63..9..";
+        let mut sudoku = Sudoku::from_line(line).unwrap();
+        let solution = match crate::solve::dancing_links(sudoku) {
+            crate::solve::SolverResult::Solved(solution) => solution,
+            _ => panic!("no solution"),
+        };
+        let mut pipeline = Pipeline::standard(SolveOptions::default());
+        pipeline.remove_kind(StrategyKind::Forcing);
+        let (won, _, _) = compute_solve1(&mut sudoku, &pipeline);
+        assert!(!won);
+        assert!(!compute_forcing_chains(&mut sudoku).is_empty());
+        assert!(sudoku.is_consistent_with(&solution));
+    }
+
+    #[test]
+    fn test_forcing_before_guessing() {
+        let line =
+            "8....51..1..6.83.9.7..3..5..5.......3..9.....4...5.6.1.1..6......3.1.47...63..9..";
+        let techniques = |pipeline: &Pipeline| -> Vec<String> {
+            let mut sudoku = Sudoku::from_line(line).unwrap();
+            let (won, _, steps) =
+                compute_solve_tree(&mut sudoku, pipeline, CellSelection::MinRemaining);
+            assert!(won);
+            steps.into_iter().map(|step| step.technique).collect()
+        };
+        // the forcing chains solve it without guessing
+        let mut pipeline = Pipeline::standard(SolveOptions::default());
+        let steps = techniques(&pipeline);
+        assert!(steps
+            .iter()
+            .any(|t| t.ends_with("Forcing Chain") || t == "Nishio"));
+        assert!(!steps.iter().any(|t| t == "Guess"));
+        pipeline.remove_kind(StrategyKind::Forcing);
+        assert!(techniques(&pipeline).iter().any(|t| t == "Guess"));
+    }
+}
@@ -0,0 +1,485 @@
+//! Puzzle generation
+//!
+//! A random complete grid is found by a depth-first search that tries the candidates of each
+//! cell in random order. Then the clues are removed one at a time in random order, each one
+//! only if the solution stays unique.
+//!
+//! With a symmetry, the clues are removed in groups of cells that map onto each other. The
+//! clue count can be kept within a range, and the puzzle can be required to be minimal: no
+//! clue can be removed without losing uniqueness. Without a symmetry the puzzles are always
+//! minimal. With one they often aren't, and a minimal symmetric puzzle can take many grids.
+//! Grids that don't meet the options are thrown away and the next one is tried, up to a number
+//! of attempts.
+//!
+//! For a target difficulty, each puzzle is rated. Puzzles that are too hard get clues of the
+//! solution back until they fit, puzzles that are too easy are thrown away.
+//!
+//! The blocks can be replaced by the regions of another layout, e.g. for jigsaw puzzles, and
+//! the layout can add extra houses, e.g. the diagonals of Sudoku-X.
+//!
+//! Everything random comes from a seeded `Rng`, so the same seed gives the same puzzle.
+
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use crate::base::{compute_exclude, Board, CellSelection, Ix, Layout, NumberSet, Sudoku};
+use crate::examples::EMPTY_SUDOKU;
+use crate::rating::{rate, Rating};
+use crate::singles::exclude_solved;
+use crate::solve::has_unique_solution;
+use crate::strategy::{Pipeline, SolveOptions};
+
+/// A small xorshift random number generator
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // splitmix64, so that similar seeds give unrelated states and 0 is fine too
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        Self { state: z | 1 }
+    }
+
+    /// xorshift64*
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A number in 0..n
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Fisher-Yates shuffle
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// Symmetry of the pattern of clues
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Symmetry {
+    None,
+    /// Unchanged by a half turn
+    Rotational180,
+    /// Unchanged by a quarter turn
+    Rotational90,
+    /// Unchanged by swapping left and right
+    Mirror,
+    /// Unchanged by swapping rows and columns
+    Diagonal,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 5] = [
+        Symmetry::None,
+        Symmetry::Rotational180,
+        Symmetry::Rotational90,
+        Symmetry::Mirror,
+        Symmetry::Diagonal,
+    ];
+
+    /// The cells the symmetry maps the cell onto, the cell itself included, sorted
+    pub fn orbit(self, (r, c): (Ix, Ix)) -> Vec<(Ix, Ix)> {
+        let flip = |i: Ix| Ix::ALL_INDICES[8 - usize::from(i)];
+        let mut cells = match self {
+            Symmetry::None => vec![(r, c)],
+            Symmetry::Rotational180 => vec![(r, c), (flip(r), flip(c))],
+            Symmetry::Rotational90 => vec![(r, c), (c, flip(r)), (flip(r), flip(c)), (flip(c), r)],
+            Symmetry::Mirror => vec![(r, c), (r, flip(c))],
+            Symmetry::Diagonal => vec![(r, c), (c, r)],
+        };
+        cells.sort();
+        cells.dedup();
+        cells
+    }
+
+    /// Checks if the clues of the puzzle have the symmetry
+    pub fn matches(self, puzzle: &Sudoku<NumberSet>) -> bool {
+        puzzle.iter_with_index().all(|(r, c, cell)| {
+            self.orbit((r, c))
+                .iter()
+                .all(|&(r2, c2)| puzzle.get(r2, c2).is_singleton() == cell.is_singleton())
+        })
+    }
+}
+
+/// Options for the puzzle generator
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GeneratorOptions {
+    pub symmetry: Symmetry,
+    /// Clues are only removed while there are more than this
+    pub min_clues: usize,
+    /// Puzzles with more clues than this are thrown away
+    pub max_clues: usize,
+    /// Only accept puzzles from which no clue can be removed. With a symmetry, this often takes
+    /// many attempts: clues are removed an orbit at a time, and single clues may still be
+    /// removable afterwards.
+    pub minimal: bool,
+    /// The number of complete grids that are tried before giving up
+    pub max_attempts: usize,
+    /// The houses of the puzzle, 3x3 blocks by default. Must be 9x9, e.g. the regions of a
+    /// jigsaw sudoku or the extra diagonals of Sudoku-X.
+    pub layout: &'static Layout,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        Self {
+            symmetry: Symmetry::None,
+            min_clues: 17,
+            max_clues: 81,
+            minimal: false,
+            max_attempts: 100,
+            layout: Layout::boxes(3, 3).intern(),
+        }
+    }
+}
+
+/// The difficulty a generated puzzle should have
+#[derive(Clone, Debug, PartialEq)]
+pub enum DifficultyTarget {
+    /// A rating score within the range, both ends included
+    Score { min: f32, max: f32 },
+    /// Solvable with the named strategies of the standard pipeline alone, e.g. singles and
+    /// pairs: `["Hidden Single", "Naked Single", "Naked Pair", "Hidden Pair"]`
+    Techniques(Vec<&'static str>),
+}
+
+impl DifficultyTarget {
+    /// The strategies of the standard pipeline a puzzle has to be solvable with, empty for a
+    /// score. Returns an error for names that aren't in the standard pipeline.
+    fn techniques(&self) -> Result<Pipeline, String> {
+        match self {
+            DifficultyTarget::Score { .. } => Ok(Pipeline::new()),
+            DifficultyTarget::Techniques(names) => {
+                let mut pipeline = Pipeline::standard(SolveOptions::default());
+                pipeline.retain_names(names)?;
+                Ok(pipeline)
+            }
+        }
+    }
+
+    /// The rating if the puzzle has the target difficulty, otherwise Less if it is too easy
+    /// and Greater if it is too hard. `techniques` is the pipeline from `techniques`, and the
+    /// rating of a technique target comes from its steps rather than from another solve.
+    fn compare(
+        &self,
+        puzzle: &Sudoku<NumberSet>,
+        techniques: &Pipeline,
+    ) -> Result<Rating, Ordering> {
+        match self {
+            DifficultyTarget::Score { min, max } => {
+                let rating = rate(puzzle);
+                if rating.score < *min {
+                    Err(Ordering::Less)
+                } else if rating.score > *max {
+                    Err(Ordering::Greater)
+                } else {
+                    Ok(rating)
+                }
+            }
+            DifficultyTarget::Techniques(_) => {
+                let mut solver_state = exclude_solved(puzzle);
+                let steps = techniques.run(&mut solver_state);
+                if solver_state.is_solved() {
+                    Ok(Rating::from_steps(steps, true))
+                } else {
+                    Err(Ordering::Greater)
+                }
+            }
+        }
+    }
+}
+
+/// A puzzle with a unique solution
+#[derive(Copy, Clone, PartialEq)]
+pub struct Generated {
+    pub puzzle: Sudoku<NumberSet>,
+    pub solution: Sudoku<NumberSet>,
+}
+
+fn fill(mut sudoku: Sudoku<NumberSet>, rng: &mut Rng) -> Option<Sudoku<NumberSet>> {
+    compute_exclude(&mut sudoku);
+    if sudoku.is_solved() {
+        return Some(sudoku);
+    }
+    if sudoku.is_invalid() {
+        return None;
+    }
+    let (r, c) = CellSelection::MinRemaining.select(&sudoku)?;
+    let mut numbers: Vec<NumberSet> = sudoku.get(r, c).iter().collect();
+    rng.shuffle(&mut numbers);
+    numbers.into_iter().find_map(|number| {
+        let mut sudoku_down = sudoku;
+        *sudoku_down.get_mut(r, c) = number;
+        fill(sudoku_down, rng)
+    })
+}
+
+/// A random complete grid
+pub fn random_solution(rng: &mut Rng) -> Sudoku<NumberSet> {
+    let empty = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+    fill(empty, rng).expect("an empty grid can be filled")
+}
+
+pub fn clue_count(puzzle: &Sudoku<NumberSet>) -> usize {
+    puzzle.iter().filter(|cell| cell.is_singleton()).count()
+}
+
+/// Checks if removing any single clue of the puzzle gives more than one solution
+pub fn is_minimal(puzzle: &Sudoku<NumberSet>) -> bool {
+    puzzle
+        .iter_with_index()
+        .filter(|(_, _, cell)| cell.is_singleton())
+        .all(|(r, c, _)| {
+            let mut removed = *puzzle;
+            *removed.get_mut(r, c) = NumberSet::all();
+            !has_unique_solution(removed).is_unique()
+        })
+}
+
+/// Removes the groups of clues in the given order, each one only if the solution stays unique
+/// and at least `min_clues` are left
+fn remove_clues(
+    mut puzzle: Sudoku<NumberSet>,
+    orbits: &[Vec<(Ix, Ix)>],
+    min_clues: usize,
+) -> Sudoku<NumberSet> {
+    let mut clues = clue_count(&puzzle);
+    for orbit in orbits {
+        if clues < min_clues + orbit.len() {
+            continue;
+        }
+        let mut removed = puzzle;
+        for &(r, c) in orbit {
+            *removed.get_mut(r, c) = NumberSet::all();
+        }
+        if has_unique_solution(removed).is_unique() {
+            puzzle = removed;
+            clues -= orbit.len();
+        }
+    }
+    puzzle
+}
+
+/// Generates a puzzle with a unique solution from the seed
+pub fn generate(seed: u64) -> Generated {
+    generate_with(seed, &GeneratorOptions::default())
+        .expect("the default options accept any puzzle")
+}
+
+/// Generates a puzzle that meets the options, None if none was found within the attempts or
+/// the layout is not 9x9 or has no solution
+pub fn generate_with(seed: u64, options: &GeneratorOptions) -> Option<Generated> {
+    let empty = Sudoku::from_str(EMPTY_SUDOKU)
+        .and_then(|empty| empty.with_layout(options.layout.clone()))
+        .ok()?;
+    let mut rng = Rng::new(seed);
+    for _ in 0..options.max_attempts {
+        let solution = fill(empty, &mut rng)?;
+        let mut orbits: Vec<Vec<(Ix, Ix)>> = solution
+            .iter_with_index()
+            .map(|(r, c, _)| options.symmetry.orbit((r, c)))
+            .collect();
+        orbits.sort();
+        orbits.dedup();
+        rng.shuffle(&mut orbits);
+        let puzzle = remove_clues(solution, &orbits, options.min_clues);
+        if clue_count(&puzzle) <= options.max_clues && (!options.minimal || is_minimal(&puzzle)) {
+            return Some(Generated { puzzle, solution });
+        }
+    }
+    None
+}
+
+/// Generates a puzzle that meets the options and has the target difficulty, None if none was
+/// found within the attempts. Puzzles that are too hard are made easier by adding clues back,
+/// unless they have to be minimal. Returns an error if the target names an unknown technique.
+pub fn generate_rated(
+    seed: u64,
+    options: &GeneratorOptions,
+    target: &DifficultyTarget,
+) -> Result<Option<(Generated, Rating)>, String> {
+    let techniques = target.techniques()?;
+    let mut rng = Rng::new(seed);
+    let single_attempt = GeneratorOptions {
+        max_attempts: 1,
+        ..*options
+    };
+    for _ in 0..options.max_attempts {
+        let mut generated = match generate_with(rng.next_u64(), &single_attempt) {
+            Some(generated) => generated,
+            None => continue,
+        };
+        loop {
+            match target.compare(&generated.puzzle, &techniques) {
+                Ok(rating) => return Ok(Some((generated, rating))),
+                Err(Ordering::Less) => break,
+                Err(_) if options.minimal => break,
+                Err(_) => {
+                    let empty: Vec<(Ix, Ix)> = generated
+                        .puzzle
+                        .iter_with_index()
+                        .filter(|(_, _, cell)| !cell.is_singleton())
+                        .map(|(r, c, _)| (r, c))
+                        .collect();
+                    let orbit = options.symmetry.orbit(empty[rng.below(empty.len())]);
+                    if clue_count(&generated.puzzle) + orbit.len() > options.max_clues {
+                        break;
+                    }
+                    for (r, c) in orbit {
+                        *generated.puzzle.get_mut(r, c) = *generated.solution.get(r, c);
+                    }
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{Ix1, Ix2, Ix5, Ix8, Ix9, RegionType};
+    use crate::examples::JIGSAW_REGIONS;
+
+    #[test]
+    fn test_rng() {
+        let mut a = Rng::new(0);
+        let mut b = Rng::new(0);
+        let mut c = Rng::new(1);
+        let first: Vec<u64> = (0..4).map(|_| a.next_u64()).collect();
+        assert_eq!(first, (0..4).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(first, (0..4).map(|_| c.next_u64()).collect::<Vec<_>>());
+        assert!((0..100).all(|_| a.below(9) < 9));
+    }
+
+    #[test]
+    fn test_generate() {
+        let generated = generate(42);
+        assert!(generated.solution.is_solved());
+        assert!(generated.puzzle.is_consistent_with(&generated.solution));
+        assert!(has_unique_solution(generated.puzzle).is_unique());
+        assert!(generated.puzzle.to_line().contains('.'));
+        assert!(generate(42) == generated);
+        assert!(generate(43) != generated);
+        assert!(is_minimal(&generated.puzzle));
+    }
+
+    #[test]
+    fn test_generator_options() {
+        assert_eq!(
+            Symmetry::Rotational90.orbit((Ix1, Ix2)),
+            vec![(Ix1, Ix2), (Ix2, Ix9), (Ix8, Ix1), (Ix9, Ix8)]
+        );
+        assert_eq!(Symmetry::Diagonal.orbit((Ix5, Ix5)), vec![(Ix5, Ix5)]);
+
+        let options = GeneratorOptions {
+            symmetry: Symmetry::Rotational180,
+            min_clues: 26,
+            max_clues: 32,
+            ..GeneratorOptions::default()
+        };
+        let generated = generate_with(7, &options).unwrap();
+        assert!(Symmetry::Rotational180.matches(&generated.puzzle));
+        let clues = clue_count(&generated.puzzle);
+        assert!((26..=32).contains(&clues));
+        assert!(has_unique_solution(generated.puzzle).is_unique());
+
+        let options = GeneratorOptions {
+            symmetry: Symmetry::Mirror,
+            minimal: true,
+            ..GeneratorOptions::default()
+        };
+        let generated = generate_with(5, &options).unwrap();
+        assert!(is_minimal(&generated.puzzle));
+        assert!(Symmetry::Mirror.matches(&generated.puzzle));
+
+        let options = GeneratorOptions {
+            symmetry: Symmetry::Rotational90,
+            minimal: true,
+            max_attempts: 3,
+            ..GeneratorOptions::default()
+        };
+        assert!(generate_with(0, &options).is_none());
+    }
+
+    #[test]
+    fn test_generate_jigsaw() {
+        let layout = Layout::jigsaw(9, JIGSAW_REGIONS).unwrap().intern();
+        let options = GeneratorOptions {
+            layout,
+            ..GeneratorOptions::default()
+        };
+        let generated = generate_with(3, &options).unwrap();
+        assert!(std::ptr::eq(generated.puzzle.layout(), layout));
+        assert!(generated.solution.is_solved());
+        assert!(has_unique_solution(generated.puzzle).is_unique());
+        // the solution breaks the 3x3 blocks
+        let standard = Sudoku::from_line(&generated.solution.to_line()).unwrap();
+        assert!(!standard.is_solved());
+    }
+
+    #[test]
+    fn test_generate_diagonals() {
+        let layout = Layout::boxes(3, 3).with_diagonals().unwrap().intern();
+        let options = GeneratorOptions {
+            layout,
+            ..GeneratorOptions::default()
+        };
+        let generated = generate_with(1, &options).unwrap();
+        assert!(generated.solution.is_solved());
+        assert!(has_unique_solution(generated.puzzle).is_unique());
+        let diagonal = generated.solution.houses().last().unwrap();
+        assert_eq!(diagonal.kind(), RegionType::Diagonal);
+    }
+
+    #[test]
+    fn test_generate_rated() {
+        let options = GeneratorOptions::default();
+        let easy = DifficultyTarget::Score { min: 1.0, max: 2.6 };
+        let (generated, rating) = generate_rated(1, &options, &easy).unwrap().unwrap();
+        assert!(rating.score <= 2.6);
+        assert!(has_unique_solution(generated.puzzle).is_unique());
+
+        let pairs = DifficultyTarget::Techniques(vec![
+            "Hidden Single",
+            "Naked Single",
+            "Naked Pair",
+            "Hidden Pair",
+        ]);
+        let (_, rating) = generate_rated(2, &options, &pairs).unwrap().unwrap();
+        assert!(rating
+            .counts
+            .keys()
+            .all(|technique| technique.ends_with("Single") || technique.ends_with("Pair")));
+
+        // nothing rates above a guess
+        let impossible = DifficultyTarget::Score {
+            min: 10.5,
+            max: 11.0,
+        };
+        let options = GeneratorOptions {
+            max_attempts: 2,
+            ..options
+        };
+        assert!(generate_rated(3, &options, &impossible).unwrap().is_none());
+
+        let misspelled = DifficultyTarget::Techniques(vec!["Hidden Single", "Naked Singles"]);
+        assert_eq!(
+            generate_rated(4, &options, &misspelled).err(),
+            Some("Unknown strategy: Naked Singles".to_string())
+        );
+    }
+}
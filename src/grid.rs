@@ -0,0 +1,481 @@
+//! Grids of any size: 4x4, 6x6, 12x12, 16x16 (hexadoku), 25x25 and so on
+//!
+//! `Grid<BW, BH>` has boxes that are BW cells wide and BH cells high, so it has N = BW * BH
+//! rows, columns, boxes and numbers. Candidates are a bitmask of up to 32 numbers. The cells
+//! are stored in a Vec, because array lengths computed from const parameters are not
+//! available on stable Rust.
+//!
+//! `Grid` and the 9x9 `Sudoku<NumberSet>` both implement `base::Board`, so `compute_exclude`,
+//! `compute_take_cell`, `compute_solve1` and `compute_solve_tree` with `Singles`, and the
+//! solvers of `solve` work on every size. The pattern techniques, the strategy pipeline, hints,
+//! rating and generation stay with `Sudoku`. `Grid<3, 3>` converts to and from
+//! `Sudoku<NumberSet>` with its layout, so 9x9 puzzles can go both ways. The way back fails
+//! for layouts that `Sudoku` can't hold.
+//!
+//! The houses of a grid are listed by a `base::Layout`. Besides the boxes it can describe the
+//! irregular regions of a jigsaw sudoku and extra houses for variants like Sudoku-X.
+
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::ops::{BitAnd, BitOr, Sub};
+
+use crate::base::{Board, CandidateSet, Ix, Layout, NumberSet, Sudoku};
+
+/// 4x4 with 2x2 boxes
+pub type Grid4 = Grid<2, 2>;
+/// 6x6 with boxes 3 wide and 2 high
+pub type Grid6 = Grid<3, 2>;
+pub type Grid9 = Grid<3, 3>;
+/// 12x12 with boxes 4 wide and 3 high
+pub type Grid12 = Grid<4, 3>;
+pub type Grid16 = Grid<4, 4>;
+pub type Grid25 = Grid<5, 5>;
+
+/// The numbers written as characters: 1 to 9, then A to Z
+const SYMBOLS: &str = "123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// A set of numbers from 1 to 32
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Candidates(u32);
+
+impl Candidates {
+    pub const NONE: Candidates = Candidates(0);
+
+    /// The numbers 1 to n, n can be at most 32
+    pub fn all(n: usize) -> Self {
+        assert!(n <= 32, "candidates can hold at most 32 numbers");
+        Candidates((1u64 << n).wrapping_sub(1) as u32)
+    }
+
+    pub fn single(number: usize) -> Self {
+        Candidates(1 << (number - 1))
+    }
+
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_singleton(self) -> bool {
+        self.0.count_ones() == 1
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn contains(self, number: usize) -> bool {
+        self.0 & (1 << (number - 1)) != 0
+    }
+
+    pub fn remove(&mut self, other: Candidates) {
+        self.0 &= !other.0;
+    }
+
+    /// The number of a singleton
+    pub fn value(self) -> Option<usize> {
+        match self.is_singleton() {
+            true => Some(self.0.trailing_zeros() as usize + 1),
+            false => None,
+        }
+    }
+
+    /// The numbers in this set, in ascending order
+    pub fn iter(self) -> impl Iterator<Item = usize> {
+        (0..32)
+            .filter(move |i| self.0 & (1 << i) != 0)
+            .map(|i| i + 1)
+    }
+}
+
+impl BitOr for Candidates {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Candidates(self.0 | other.0)
+    }
+}
+
+impl BitAnd for Candidates {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        Candidates(self.0 & other.0)
+    }
+}
+
+impl Sub for Candidates {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Candidates(self.0 & !other.0)
+    }
+}
+
+impl CandidateSet for Candidates {
+    const NONE: Self = Candidates::NONE;
+
+    fn is_singleton(&self) -> bool {
+        Candidates::is_singleton(*self)
+    }
+
+    fn len(&self) -> usize {
+        Candidates::len(*self)
+    }
+
+    fn singletons(self) -> impl Iterator<Item = Self> {
+        self.iter().map(Candidates::single)
+    }
+}
+
+impl From<NumberSet> for Candidates {
+    fn from(numbers: NumberSet) -> Self {
+        Candidates(u32::from(numbers.bits()))
+    }
+}
+
+impl TryFrom<Candidates> for NumberSet {
+    type Error = String;
+
+    fn try_from(candidates: Candidates) -> Result<Self, Self::Error> {
+        u16::try_from(candidates.0)
+            .ok()
+            .and_then(NumberSet::from_bits)
+            .ok_or_else(|| format!("Not a 9x9 candidate set: {:#b}", candidates.0))
+    }
+}
+
+/// A grid of candidate sets with boxes BW cells wide and BH cells high
+///
+/// The boxes can be replaced by other regions, or extra houses added, with `with_layout`.
+#[derive(Clone, Debug, Eq)]
+pub struct Grid<const BW: usize, const BH: usize> {
+    // row-major
+    cells: Vec<Candidates>,
+    // interned, so copies made while searching share it
+    layout: &'static Layout,
+}
+
+impl<const BW: usize, const BH: usize> Default for Grid<BW, BH> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Layouts are interned, so equal layouts are the same reference
+impl<const BW: usize, const BH: usize> PartialEq for Grid<BW, BH> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cells == other.cells && std::ptr::eq(self.layout, other.layout)
+    }
+}
+
+impl<const BW: usize, const BH: usize> Grid<BW, BH> {
+    /// The number of rows, columns, boxes and numbers. `Candidates` holds at most 32 numbers,
+    /// so larger grids do not compile.
+    pub const SIZE: usize = {
+        assert!(BW * BH <= 32, "a grid can have at most 32 numbers");
+        BW * BH
+    };
+
+    /// An empty grid: every cell has all candidates
+    pub fn new() -> Self {
+        Self {
+            cells: vec![Candidates::all(Self::SIZE); Self::SIZE * Self::SIZE],
+            layout: Layout::boxes(BW, BH).intern(),
+        }
+    }
+
+    /// Replaces the layout, e.g. by the regions of a jigsaw sudoku. Returns an error if the
+    /// layout has the wrong size.
+    pub fn with_layout(mut self, layout: Layout) -> Result<Self, String> {
+        if layout.size() != Self::SIZE {
+            return Err(format!(
+                "Expected a {0}x{0} layout, got {1}x{1}",
+                Self::SIZE,
+                layout.size()
+            ));
+        }
+        self.layout = layout.intern();
+        Ok(self)
+    }
+
+    pub fn get(&self, r: usize, c: usize) -> Candidates {
+        self.cells[r * Self::SIZE + c]
+    }
+
+    pub fn get_mut(&mut self, r: usize, c: usize) -> &mut Candidates {
+        &mut self.cells[r * Self::SIZE + c]
+    }
+
+    /// Parses the single line format: one character per cell, '.' or '0' for an empty cell
+    pub fn from_line(s: &str) -> Result<Self, String> {
+        let n = Self::SIZE;
+        let chars: Vec<char> = s.trim().chars().collect();
+        if chars.len() != n * n {
+            return Err(format!("Expected {} cells, got {}", n * n, chars.len()));
+        }
+        let mut grid = Self::new();
+        for (cell, &value) in grid.cells.iter_mut().zip(&chars) {
+            if value == '.' || value == '0' {
+                continue;
+            }
+            match SYMBOLS.chars().take(n).position(|symbol| symbol == value) {
+                Some(i) => *cell = Candidates::single(i + 1),
+                None => return Err(format!("Invalid cell value: {}", value)),
+            }
+        }
+        Ok(grid)
+    }
+
+    /// Formats the grid in the single line format, unsolved cells are written as '.'
+    pub fn to_line(&self) -> String {
+        self.cells
+            .iter()
+            .map(|cell| match cell.value() {
+                Some(number) => SYMBOLS.chars().nth(number - 1).unwrap(),
+                None => '.',
+            })
+            .collect()
+    }
+}
+
+/// One line per row
+impl<const BW: usize, const BH: usize> Display for Grid<BW, BH> {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let line = self.to_line();
+        let chars: Vec<char> = line.chars().collect();
+        for row in chars.chunks(Self::SIZE) {
+            writeln!(formatter, "{}", row.iter().collect::<String>())?;
+        }
+        Ok(())
+    }
+}
+
+impl<const BW: usize, const BH: usize> Board for Grid<BW, BH> {
+    type Set = Candidates;
+
+    fn layout(&self) -> &'static Layout {
+        self.layout
+    }
+
+    fn cell(&self, r: usize, c: usize) -> Candidates {
+        self.get(r, c)
+    }
+
+    fn cell_mut(&mut self, r: usize, c: usize) -> &mut Candidates {
+        self.get_mut(r, c)
+    }
+
+    fn all_numbers(&self) -> Candidates {
+        Candidates::all(Self::SIZE)
+    }
+}
+
+impl From<&Sudoku<NumberSet>> for Grid9 {
+    fn from(sudoku: &Sudoku<NumberSet>) -> Self {
+        let mut grid = Self {
+            layout: sudoku.layout(),
+            ..Self::new()
+        };
+        for (r, c, &cell) in sudoku.iter_with_index() {
+            *grid.get_mut(usize::from(r), usize::from(c)) = Candidates::from(cell);
+        }
+        grid
+    }
+}
+
+/// Returns an error if the layout has more than 9 houses of a kind
+impl TryFrom<&Grid9> for Sudoku<NumberSet> {
+    type Error = String;
+
+    fn try_from(grid: &Grid9) -> Result<Self, Self::Error> {
+        let mut sudoku = Sudoku::from_line(&".".repeat(81))?.with_layout(grid.layout().clone())?;
+        for r in Ix::all_indices() {
+            for c in Ix::all_indices() {
+                let cell = grid.get(usize::from(r), usize::from(c));
+                *sudoku.get_mut(r, c) = NumberSet::try_from(cell)?;
+            }
+        }
+        Ok(sudoku)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{compute_solve_tree, CellSelection, RegionType, Singles};
+    use crate::examples::*;
+    use crate::solve::{count_solutions, dancing_links, SolverResult};
+    use std::str::FromStr;
+
+    fn grid_solve<B: Board>(grid: &B) -> Option<B> {
+        let mut solution = grid.clone();
+        match compute_solve_tree(&mut solution, &Singles, CellSelection::MinRemaining) {
+            (true, _, _) => Some(solution),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_houses() {
+        assert_eq!(Grid6::SIZE, 6);
+        let layout = Layout::boxes(3, 2);
+        let houses = layout.houses();
+        assert_eq!(houses.len(), 18);
+        // the second box of a 6x6 grid is in the top right
+        assert_eq!(
+            houses[13],
+            vec![(0, 3), (0, 4), (0, 5), (1, 3), (1, 4), (1, 5)]
+        );
+        assert_eq!(layout.peers(0, 0).len(), 5 + 5 + 2);
+        assert_eq!(Grid16::new().layout().peers(0, 0).len(), 15 + 15 + 9);
+    }
+
+    #[test]
+    fn test_solve_sizes() {
+        let grid = Grid4::from_line("1....2....3....4").unwrap();
+        let solution = grid_solve(&grid).unwrap();
+        assert!(solution.is_solved());
+        assert_eq!(count_solutions(grid.clone(), 10), 2);
+
+        let grid = Grid6::from_line("1.....4.....2.....5.....3.....6.....").unwrap();
+        assert!(grid_solve(&grid).unwrap().is_solved());
+        match dancing_links(grid) {
+            SolverResult::Solved(solution) => assert!(solution.is_solved()),
+            SolverResult::Contradiction(_) => panic!("no solution"),
+        }
+
+        let solution = grid_solve(&Grid16::new()).unwrap();
+        assert!(solution.is_solved());
+        assert_eq!(Grid16::from_line(&solution.to_line()).unwrap(), solution);
+        assert!(Grid12::from_line("G").is_err());
+    }
+
+    #[test]
+    fn test_convert_9x9() {
+        let sudoku = Sudoku::from_str(SUDOKU1).unwrap();
+        let grid = Grid9::from(&sudoku);
+        assert_eq!(grid.to_line(), sudoku.to_line());
+        let solution = grid_solve(&grid).unwrap();
+        match dancing_links(sudoku) {
+            SolverResult::Solved(expected) => {
+                assert!(Sudoku::try_from(&solution).unwrap() == expected)
+            }
+            _ => panic!("no solution"),
+        }
+        assert_eq!(count_solutions(grid.clone(), 2), 1);
+
+        // a grid can have more extra houses of a kind than a sudoku
+        let rows = (0..10).map(|k| (0..9).map(|c| (k % 9, c)).collect::<Vec<_>>());
+        let layout = Layout::boxes(3, 3).with_houses(RegionType::Window, rows);
+        let grid = Grid9::new().with_layout(layout.unwrap()).unwrap();
+        assert_eq!(
+            Sudoku::try_from(&grid).err(),
+            Some("Expected at most 9 houses of kind Window".to_string())
+        );
+    }
+
+    #[test]
+    fn test_jigsaw() {
+        // the top left and top middle regions trade r1c3 and r2c4
+        let map = "AABBBBCCC AAAABBCCC AAABBBCCC DDDEEEFFF DDDEEEFFF DDDEEEFFF \
+                   GGGHHHIII GGGHHHIII GGGHHHIII";
+        let layout = Layout::jigsaw(9, map).unwrap();
+        assert!(layout.houses()[18].contains(&(1, 3)));
+        assert!(!layout.houses()[18].contains(&(0, 2)));
+        assert!(layout.peers(2, 0).contains(&(1, 3)));
+        assert!(!Layout::boxes(3, 3).peers(2, 0).contains(&(1, 3)));
+
+        // SOLVED_SUDOKU has the same number in r1c3 and r2c4, so it fits both layouts
+        let line = Sudoku::from_str(SOLVED_SUDOKU).unwrap().to_line();
+        let puzzle: String = line
+            .chars()
+            .enumerate()
+            .map(|(i, value)| if i % 3 == 0 { '.' } else { value })
+            .collect();
+        let grid = Grid9::from_line(&puzzle)
+            .unwrap()
+            .with_layout(layout.clone())
+            .unwrap();
+        assert_eq!(count_solutions(grid.clone(), 2), 1);
+        let solution = grid_solve(&grid).unwrap();
+        assert_eq!(solution.to_line(), line);
+        assert_eq!(solution.layout(), &layout);
+
+        let disconnected = map.replacen("AAAAB", "AAABA", 1);
+        assert!(Layout::jigsaw(9, &disconnected).is_err());
+        let wrong_size = map.replacen("AAAAB", "AAAAA", 1);
+        assert!(Layout::jigsaw(9, &wrong_size).is_err());
+        assert!(Layout::jigsaw(9, "AB").is_err());
+    }
+
+    #[test]
+    fn test_extra_houses() {
+        let x = Layout::boxes(3, 3).with_diagonals().unwrap();
+        assert_eq!(x.houses().len(), 29);
+        assert!(x.peers(0, 0).contains(&(8, 8)));
+        assert!(x.peers(0, 8).contains(&(4, 4)));
+        assert_eq!(x.peers(4, 4).len(), 20 + 12);
+
+        let windoku = Layout::boxes(3, 3).with_windows().unwrap();
+        assert_eq!(windoku.houses().len(), 31);
+        assert_eq!(windoku.houses()[30][0], (5, 5));
+        let center_dot = Layout::boxes(3, 3).with_center_dot().unwrap();
+        assert_eq!(center_dot.houses()[27][..3], [(1, 1), (1, 4), (1, 7)]);
+        let disjoint = Layout::boxes(3, 2).with_disjoint_groups().unwrap();
+        assert_eq!(disjoint.houses().len(), 24);
+        assert_eq!(
+            disjoint.houses()[23],
+            vec![(1, 2), (1, 5), (3, 2), (3, 5), (5, 2), (5, 5)]
+        );
+
+        // a house needs one cell for every number
+        let short = vec![(0, 0), (1, 1)];
+        assert!(Layout::boxes(3, 3)
+            .with_houses(RegionType::Diagonal, vec![short])
+            .is_err());
+        let repeated = vec![(0, 0); 9];
+        assert!(Layout::boxes(3, 3)
+            .with_houses(RegionType::Diagonal, vec![repeated])
+            .is_err());
+        let outside = (0..9).map(|i| (i, i + 1)).collect();
+        assert!(Layout::boxes(3, 3)
+            .with_houses(RegionType::Diagonal, vec![outside])
+            .is_err());
+        let row = (0..9).map(|c| (0, c)).collect();
+        assert!(Layout::boxes(3, 3)
+            .with_houses(RegionType::Row, vec![row])
+            .is_err());
+        let jigsaw = Layout::jigsaw(9, JIGSAW_REGIONS).unwrap();
+        assert!(jigsaw.clone().with_windows().is_err());
+        assert!(jigsaw.with_disjoint_groups().is_err());
+        assert!(Layout::boxes(2, 2).with_center_dot().is_err());
+    }
+
+    #[test]
+    fn test_solve_variants() {
+        let layout = Layout::boxes(3, 3)
+            .with_diagonals()
+            .and_then(Layout::with_windows)
+            .unwrap();
+        let grid = Grid9::new().with_layout(layout.clone()).unwrap();
+        let solution = grid_solve(&grid).unwrap();
+        assert!(solution.is_solved());
+        let standard = Grid9::from_line(&solution.to_line()).unwrap();
+        assert!(standard.is_solved());
+
+        // the solution of the plain sudoku breaks the diagonals
+        let sudoku = Grid9::from(&Sudoku::from_str(SOLVED_SUDOKU).unwrap());
+        assert!(sudoku.is_solved());
+        assert!(sudoku.with_layout(layout).unwrap().is_invalid());
+
+        let layout = Layout::boxes(2, 2).with_diagonals().unwrap();
+        assert_eq!(count_solutions(Grid4::new(), 1000), 288);
+        let grid = Grid4::new().with_layout(layout).unwrap();
+        assert_eq!(count_solutions(grid, 1000), 48);
+        assert!(Grid4::new().with_layout(Layout::boxes(3, 3)).is_err());
+    }
+}
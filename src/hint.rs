@@ -0,0 +1,187 @@
+//! Hints: the easiest next step for a partially filled grid, in three levels of detail
+//!
+//! The solved cells of the grid count as filled in. Their numbers are removed from their
+//! peers first, like `compute_exclude` does, but without going on with the cells that this
+//! solves: these are the naked singles the player should find.
+
+use crate::base::{cell_name, Board, Candidate, NumberSet, Sudoku};
+use crate::explain::{explain_step, format_numbers, Format};
+use crate::singles::{exclude_solved, find_hidden_singles};
+use crate::strategy::{Pipeline, SolveOptions, Step};
+
+/// How much a hint gives away
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HintLevel {
+    /// Where to look, e.g. "Look at block 5"
+    Area,
+    /// What to look for, e.g. "There is a Hidden Single for 7 in block 5"
+    Technique,
+    /// The whole deduction, e.g. "Hidden Single in block 5: r4c6 = 7"
+    Full,
+}
+
+impl HintLevel {
+    pub const ALL: [HintLevel; 3] = [HintLevel::Area, HintLevel::Technique, HintLevel::Full];
+}
+
+/// A hint for the next step
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hint {
+    pub step: Step,
+}
+
+impl Hint {
+    /// The houses or cells to look at, with the preposition that goes with them
+    fn area(&self) -> (&'static str, String) {
+        let step = &self.step;
+        // a naked single lists all houses of its cell, the cell itself says more
+        if step.houses.is_empty() || (step.houses.len() > 1 && !step.placements.is_empty()) {
+            let cells: Vec<String> = step.cells.iter().map(|&cell| cell_name(cell)).collect();
+            ("on", cells.join(", "))
+        } else {
+            let houses: Vec<String> = step.houses.iter().map(|h| h.to_string()).collect();
+            ("in", houses.join(", "))
+        }
+    }
+
+    pub fn text(&self, level: HintLevel) -> String {
+        let step = &self.step;
+        let (preposition, area) = self.area();
+        match level {
+            HintLevel::Area => format!("Look at {}", area),
+            HintLevel::Technique => {
+                let article = match step.technique.chars().next() {
+                    Some('A') | Some('E') | Some('I') | Some('O') | Some('X') => "an",
+                    _ => "a",
+                };
+                let numbers = if step.numbers.is_empty() {
+                    String::new()
+                } else {
+                    format!(" for {}", format_numbers(step.numbers))
+                };
+                format!(
+                    "There is {} {}{} {} {}",
+                    article, step.technique, numbers, preposition, area
+                )
+            }
+            HintLevel::Full => explain_step(step, Format::PlainText),
+        }
+    }
+}
+
+/// Finds the easiest next step of the standard pipeline
+pub fn next_hint(grid: &Sudoku<NumberSet>) -> Option<Hint> {
+    next_hint_with(grid, &Pipeline::standard(SolveOptions::default()))
+}
+
+/// Finds the easiest next step: hidden singles, then naked singles, then the strategies of the
+/// pipeline in their order. Returns None if the grid is solved, contradictory or too hard for
+/// the pipeline.
+pub fn next_hint_with(grid: &Sudoku<NumberSet>, pipeline: &Pipeline) -> Option<Hint> {
+    if grid.is_solved() || !grid.has_no_contradiction() {
+        return None;
+    }
+    let state = exclude_solved(grid);
+    if !state.has_no_contradiction() {
+        return None;
+    }
+
+    let hidden = find_hidden_singles(&state)
+        .into_iter()
+        .next()
+        .map(|single| {
+            let mut step = Step::new(
+                "Hidden Single",
+                single.house.into_iter().collect(),
+                vec![single.placement.cell()],
+                single.eliminations,
+            );
+            step.numbers = single.placement.number;
+            step.placements.push(single.placement);
+            step
+        });
+    let naked = || {
+        state
+            .iter_with_index()
+            .find(|&(r, c, cell)| cell.is_singleton() && !grid.get(r, c).is_singleton())
+            .map(|(r, c, &number)| {
+                let mut step = Step::new(
+                    "Naked Single",
+                    state.houses_for_cell(r, c).collect(),
+                    vec![(r, c)],
+                    Vec::new(),
+                );
+                step.numbers = number;
+                step.placements.push(Candidate::new(r, c, number));
+                step
+            })
+    };
+    let step = hidden
+        .or_else(naked)
+        .or_else(|| pipeline.step(&mut state.clone()))?;
+    Some(Hint { step })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{Ix1, Ix2, Ix3, Ix4, Ix5, Ix6, Ix7};
+    use crate::examples::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_hidden_single_hint() {
+        let mut grid = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        // the 9s leave only r1c1 for a 9 in row 1
+        for &(r, c) in [(Ix2, Ix4), (Ix3, Ix7), (Ix4, Ix2), (Ix7, Ix3)].iter() {
+            *grid.get_mut(r, c) = NumberSet::N9;
+        }
+        let hint = next_hint(&grid).unwrap();
+        assert_eq!(hint.text(HintLevel::Area), "Look at row 1");
+        assert_eq!(
+            hint.text(HintLevel::Technique),
+            "There is a Hidden Single for 9 in row 1"
+        );
+        assert_eq!(
+            hint.text(HintLevel::Full),
+            "Hidden Single in row 1: r1c1 = 9"
+        );
+        assert_eq!(
+            hint.step.placements,
+            vec![Candidate::new(Ix1, Ix1, NumberSet::N9)]
+        );
+    }
+
+    #[test]
+    fn test_naked_single_hint() {
+        let mut grid = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        let givens = [
+            (Ix5, Ix1, NumberSet::N1),
+            (Ix5, Ix2, NumberSet::N2),
+            (Ix5, Ix3, NumberSet::N3),
+            (Ix1, Ix5, NumberSet::N4),
+            (Ix2, Ix5, NumberSet::N5),
+            (Ix3, Ix5, NumberSet::N6),
+            (Ix4, Ix4, NumberSet::N7),
+            (Ix6, Ix6, NumberSet::N8),
+        ];
+        for &(r, c, number) in givens.iter() {
+            *grid.get_mut(r, c) = number;
+        }
+        let hint = next_hint(&grid).unwrap();
+        assert_eq!(hint.step.technique, "Naked Single");
+        assert_eq!(hint.text(HintLevel::Area), "Look at r5c5");
+        assert_eq!(
+            hint.text(HintLevel::Technique),
+            "There is a Naked Single for 9 on r5c5"
+        );
+        assert_eq!(hint.text(HintLevel::Full), "Naked Single: r5c5 = 9");
+
+        let solved = Sudoku::from_str(SOLVED_SUDOKU).unwrap();
+        assert!(next_hint(&solved).is_none());
+        // only a guess helps with an empty grid
+        assert!(
+            next_hint_with(&Sudoku::from_str(EMPTY_SUDOKU).unwrap(), &Pipeline::new()).is_none()
+        );
+    }
+}
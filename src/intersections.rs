@@ -0,0 +1,194 @@
+//! Intersection removal (locked candidates)
+//!
+//! Pointing: if a number in a block can only go into cells of one row or column, it can be
+//! removed from the rest of that line.
+//! Claiming (box-line reduction): if a number in a row or column can only go into cells of
+//! one block, it can be removed from the rest of that block.
+//!
+//! The extra houses of the layout take part like blocks: a number confined to a diagonal
+//! or window and another house is removed from the rest of that house.
+
+use crate::base::{Elimination, House, Ix, NumberSet, RegionType, Sudoku};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum IntersectionKind {
+    Pointing,
+    Claiming,
+}
+
+/// A number confined to the intersection of two houses and the candidates it eliminates
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LockedCandidates {
+    pub kind: IntersectionKind,
+    pub number: NumberSet,
+    /// The house in which the number can only go into the intersection
+    pub base: House,
+    /// The house from which the number is removed outside of the intersection
+    pub cover: House,
+    /// The cells of the intersection that have the number as a candidate
+    pub cells: Vec<(Ix, Ix)>,
+    pub eliminations: Vec<Elimination>,
+}
+
+/// Finds numbers that are confined to the intersection of `base` with another house, and the
+/// candidates this eliminates in that other house
+fn find_locked(
+    solver_state: &Sudoku<NumberSet>,
+    kind: IntersectionKind,
+    base: House,
+) -> Vec<LockedCandidates> {
+    let mut found = Vec::new();
+    let placed = solver_state
+        .house(base)
+        .filter(|cell| cell.is_singleton())
+        .fold(NumberSet::empty(), |acc, cell| acc | *cell);
+    for number in (NumberSet::all() - placed).iter() {
+        let cells: Vec<(Ix, Ix)> = base
+            .cells()
+            .iter()
+            .cloned()
+            .filter(|&(r, c)| solver_state.get(r, c).contains(number))
+            .collect();
+        // a single cell would be a hidden single, which is handled by compute_take_cell
+        if cells.len() < 2 {
+            continue;
+        }
+        let (r, c) = cells[0];
+        let covers = solver_state
+            .houses_for_cell(r, c)
+            .filter(|&cover| cover != base && cells.iter().all(|&(r, c)| cover.contains(r, c)));
+        for cover in covers {
+            let eliminations: Vec<Elimination> = cover
+                .cells()
+                .iter()
+                .filter(|&&(r, c)| !base.contains(r, c))
+                .filter_map(|&(r, c)| solver_state.elimination(r, c, number))
+                .collect();
+            if !eliminations.is_empty() {
+                found.push(LockedCandidates {
+                    kind,
+                    number,
+                    base,
+                    cover,
+                    cells: cells.clone(),
+                    eliminations,
+                });
+            }
+        }
+    }
+    found
+}
+
+fn is_line(house: &House) -> bool {
+    house.kind() == RegionType::Row || house.kind() == RegionType::Col
+}
+
+/// Finds all pointing pairs and triples that eliminate at least one candidate: the number
+/// is confined within a block or extra house to any house it intersects
+pub fn find_pointing(solver_state: &Sudoku<NumberSet>) -> Vec<LockedCandidates> {
+    solver_state
+        .houses()
+        .filter(|h| !is_line(h))
+        .flat_map(|base| find_locked(solver_state, IntersectionKind::Pointing, base))
+        .collect()
+}
+
+/// Finds all box-line reductions that eliminate at least one candidate: the number is
+/// confined within a row or column to a block or extra house
+pub fn find_claiming(solver_state: &Sudoku<NumberSet>) -> Vec<LockedCandidates> {
+    solver_state
+        .houses()
+        .filter(is_line)
+        .flat_map(|line| find_locked(solver_state, IntersectionKind::Claiming, line))
+        .collect()
+}
+
+/// compute field constraints: remove candidates using pointing pairs and triples
+///
+/// Strategy:
+/// - for every block (/extra house) and number, check if the number's cells are all in one
+///   other house
+/// - if so, remove the number from the rest of that house
+pub fn compute_pointing(solver_state: &mut Sudoku<NumberSet>) -> Vec<LockedCandidates> {
+    let found = find_pointing(solver_state);
+    solver_state.apply_eliminations(found.iter().flat_map(|l| &l.eliminations));
+    found
+}
+
+/// compute field constraints: remove candidates using box-line reduction
+///
+/// Strategy:
+/// - for every row (/column) and number, check if the number's cells are all in one block
+///   (/extra house)
+/// - if so, remove the number from the rest of that house
+pub fn compute_claiming(solver_state: &mut Sudoku<NumberSet>) -> Vec<LockedCandidates> {
+    let found = find_claiming(solver_state);
+    solver_state.apply_eliminations(found.iter().flat_map(|l| &l.eliminations));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{Ix1, Ix2, Ix3, Ix4, Ix5, Ix9, Layout};
+    use crate::examples::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_pointing() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        // in block 1, the 5 can only go into row 1
+        for r in [Ix2, Ix3].iter() {
+            for c in [Ix1, Ix2, Ix3].iter() {
+                sudoku.eliminate(*r, *c, NumberSet::N5);
+            }
+        }
+        let found = compute_pointing(&mut sudoku);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].base, House::block(Ix1));
+        assert_eq!(found[0].cover, House::row(Ix1));
+        assert_eq!(found[0].cells.len(), 3);
+        assert_eq!(found[0].eliminations.len(), 6);
+        assert!(!sudoku.get(Ix1, Ix9).contains(NumberSet::N5));
+        assert!(sudoku.get(Ix2, Ix9).contains(NumberSet::N5));
+        assert!(find_pointing(&sudoku).is_empty());
+    }
+
+    #[test]
+    fn test_claiming() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        // in column 4, the 7 can only go into block 5
+        for r in Ix::all_indices().filter(|r| ![Ix4, Ix5].contains(r)) {
+            sudoku.eliminate(r, Ix4, NumberSet::N7);
+        }
+        let found = compute_claiming(&mut sudoku);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, IntersectionKind::Claiming);
+        assert_eq!(found[0].base, House::col(Ix4));
+        assert_eq!(found[0].cover, House::block(Ix5));
+        // the six cells of block 5 outside of column 4 lose the 7
+        assert_eq!(found[0].eliminations.len(), 6);
+    }
+
+    #[test]
+    fn test_pointing_diagonal() {
+        let layout = Layout::boxes(3, 3).with_diagonals().unwrap();
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU)
+            .unwrap()
+            .with_layout(layout)
+            .unwrap();
+        // on the main diagonal, the 5 can only go into block 1
+        for k in 3..9 {
+            let ix = Ix::ALL_INDICES[k];
+            sudoku.eliminate(ix, ix, NumberSet::N5);
+        }
+        let found = compute_pointing(&mut sudoku);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].base.kind(), RegionType::Diagonal);
+        assert_eq!(found[0].cover, House::block(Ix1));
+        assert_eq!(found[0].cells, vec![(Ix1, Ix1), (Ix2, Ix2), (Ix3, Ix3)]);
+        assert_eq!(found[0].eliminations.len(), 6);
+        assert!(!sudoku.get(Ix1, Ix3).contains(NumberSet::N5));
+        assert!(find_claiming(&sudoku).is_empty());
+    }
+}
@@ -1,3 +1,20 @@
+pub mod als;
 pub mod base;
+pub mod chains;
+pub mod coloring;
+pub mod dlx;
 pub mod examples;
+pub mod explain;
+pub mod fish;
+pub mod forcing;
+pub mod generate;
+pub mod grid;
+pub mod hint;
+pub mod intersections;
+pub mod rating;
+pub mod singles;
 pub mod solve;
+pub mod strategy;
+pub mod subsets;
+pub mod uniqueness;
+pub mod wings;
@@ -5,6 +5,8 @@ use std::str::FromStr;
 
 use std::io::Read;
 
+// reads a puzzle from stdin, kept as an alternative to main for scripting
+#[allow(dead_code)]
 fn python_solve() {
     //parse_test1();
     //solve_test1();
@@ -14,18 +16,31 @@ fn python_solve() {
     let game_state = parse_game_state(&input).unwrap();
     println!("{}", format_game_state(&game_state));
     let mut solver_state = game_to_solver_state(&game_state);
-    //let (_won, _lost) = compute_solve1(&mut solver_state, true);
-    //let (_won, _lost) = compute_solve_tree(&mut solver_state, true); // verbose=true
-    let (_won, _lost) = compute_solve_tree(&mut solver_state, false); // verbose=false
+    //let (_won, _lost, _steps) = compute_solve1(&mut solver_state, &Singles);
+    let (_won, _lost, _steps) = compute_solve_tree(&mut solver_state, &Singles, CellSelection::MinRemaining);
+    let game_state = solver_to_game_state(&solver_state);
+    println!("{}", format_game_state(&game_state));
 }
 
 fn main() {
     let sudoku_input = Sudoku::from_str(SUDOKU2).unwrap();
 
+    let timer = std::time::Instant::now();
+    let sudoku = match dancing_links(sudoku_input) {
+        SolverResult::Solved(s) => {
+            println!("Solved!");
+            s
+        }
+        SolverResult::Contradiction(s) => {
+            println!("Contradiction!");
+            s
+        }
+    };
+    println!("{}", sudoku);
+    println!("Computed in {} microseconds", timer.elapsed().as_micros());
 
     let timer = std::time::Instant::now();
-    let mut sudoku = sudoku_input.clone();
-    let sudoku = match brute_force_with_exclude(sudoku) {
+    let sudoku = match brute_force_with_exclude(sudoku_input, CellSelection::MinRemaining) {
         SolverResult::Solved(s) => {
             println!("Solved!");
             s
@@ -40,8 +55,7 @@ fn main() {
 
 
     let timer = std::time::Instant::now();
-    let mut sudoku = sudoku_input.clone();
-    let sudoku = match brute_force(sudoku) {
+    let sudoku = match brute_force(sudoku_input, CellSelection::MinRemaining) {
         SolverResult::Solved(s) => {
             println!("Solved!");
             s
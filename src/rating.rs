@@ -0,0 +1,108 @@
+//! Difficulty rating on a scale similar to the one of Sudoku Explainer (SE)
+//!
+//! The puzzle is solved with the standard pipeline, which tries the techniques from easiest to
+//! hardest. The score is the difficulty of the hardest step, e.g. 1.5 for a puzzle that only
+//! needs hidden singles, 3.2 for one that needs an X-Wing and 7.5 for one that needs a nishio.
+//! Puzzles that can only be solved by guessing score `GUESS_DIFFICULTY`.
+
+use std::collections::BTreeMap;
+
+use crate::base::{compute_solve_tree, CellSelection, NumberSet, Sudoku};
+use crate::singles::exclude_solved;
+use crate::strategy::{Pipeline, SolveOptions, Step};
+
+/// The difficulty of a puzzle
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rating {
+    /// The difficulty of the hardest step, 0 if there was nothing to do
+    pub score: f32,
+    /// The first of the hardest steps
+    pub hardest: Option<Step>,
+    /// The number of steps per technique
+    pub counts: BTreeMap<String, usize>,
+    /// True if the techniques were not enough and the solver had to guess
+    pub guessed: bool,
+    /// False if the puzzle has no solution
+    pub solved: bool,
+}
+
+/// Rates a puzzle without the techniques that assume a unique solution
+pub fn rate(puzzle: &Sudoku<NumberSet>) -> Rating {
+    rate_with(puzzle, SolveOptions::default())
+}
+
+/// Rates a puzzle. The givens are not counted as naked singles.
+pub fn rate_with(puzzle: &Sudoku<NumberSet>, mut options: SolveOptions) -> Rating {
+    options.givens.get_or_insert(*puzzle);
+    let pipeline = Pipeline::standard(options);
+    let mut solver_state = exclude_solved(puzzle);
+    let (solved, _, steps) =
+        compute_solve_tree(&mut solver_state, &pipeline, CellSelection::MinRemaining);
+    Rating::from_steps(steps, solved)
+}
+
+impl Rating {
+    /// The rating of the steps of a solve, e.g. the steps `Pipeline::run` returned
+    pub fn from_steps(steps: Vec<Step>, solved: bool) -> Self {
+        let mut counts = BTreeMap::new();
+        for step in steps.iter() {
+            *counts.entry(step.technique.clone()).or_insert(0) += 1;
+        }
+        let guessed = steps.iter().any(|step| step.technique == "Guess");
+        let hardest = steps
+            .into_iter()
+            .fold(None, |hardest: Option<Step>, step| match hardest {
+                Some(h) if h.difficulty >= step.difficulty => Some(h),
+                _ => Some(step),
+            });
+        Rating {
+            score: hardest.as_ref().map_or(0.0, |step| step.difficulty),
+            hardest,
+            counts,
+            guessed,
+            solved,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{Ix2, Ix5, Ix7, Ix8};
+    use crate::examples::*;
+    use crate::strategy::GUESS_DIFFICULTY;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_rate_easy() {
+        let rating = rate(&Sudoku::from_str(SUDOKU1).unwrap());
+        assert!(rating.solved);
+        assert!(!rating.guessed);
+        assert!(rating.score <= 2.3);
+        assert!(!rating.counts.contains_key("Guess"));
+        let solved = rate(&Sudoku::from_str(SOLVED_SUDOKU).unwrap());
+        assert_eq!(solved.score, 0.0);
+        assert!(solved.counts.is_empty());
+    }
+
+    #[test]
+    fn test_rate_hard() {
+        let line =
+            "8....51..1..6.83.9.7..3..5..5.......3..9.....4...5.6.1.1..6......3.1.47...63..9..";
+        let rating = rate(&Sudoku::from_line(line).unwrap());
+        assert!(rating.solved);
+        assert!(!rating.guessed);
+        assert!(rating.score >= 7.5);
+        let hardest = rating.hardest.unwrap();
+        assert!(rating.counts[&hardest.technique] >= 1);
+
+        // two solutions, so no technique can decide between them
+        let mut puzzle = Sudoku::from_str(SOLVED_SUDOKU).unwrap();
+        for &(r, c) in [(Ix2, Ix7), (Ix2, Ix8), (Ix5, Ix7), (Ix5, Ix8)].iter() {
+            *puzzle.get_mut(r, c) = NumberSet::all();
+        }
+        let rating = rate(&puzzle);
+        assert!(rating.guessed);
+        assert_eq!(rating.score, GUESS_DIFFICULTY);
+    }
+}
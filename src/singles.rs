@@ -0,0 +1,149 @@
+//! Naked and hidden singles, one deduction at a time
+//!
+//! `compute_exclude` and `compute_take_cell` apply all singles at once. The functions here find
+//! them one by one, so that each one can be reported as a step of its own.
+//!
+//! Naked single: a cell has only one candidate left, so the number is removed from its peers.
+//! Hidden single: a number can only go into one cell of a house, so it is placed there and
+//! removed from the peers of that cell.
+
+use crate::base::{Candidate, Elimination, House, NumberSet, Sudoku};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SingleKind {
+    Naked,
+    Hidden,
+}
+
+/// A single and the candidates that placing it eliminates
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Single {
+    pub kind: SingleKind,
+    pub placement: Candidate,
+    /// The house in which a hidden single is the only place for its number
+    pub house: Option<House>,
+    pub eliminations: Vec<Elimination>,
+}
+
+/// Eliminations of placing the candidate: the other candidates of its cell and the number in
+/// all its peers
+fn placement_eliminations(
+    solver_state: &Sudoku<NumberSet>,
+    placement: Candidate,
+) -> Vec<Elimination> {
+    let (r, c) = placement.cell();
+    let others = *solver_state.get(r, c) - placement.number;
+    solver_state
+        .elimination(r, c, others)
+        .into_iter()
+        .chain(
+            solver_state
+                .peers(r, c)
+                .filter_map(|(pr, pc)| solver_state.elimination(pr, pc, placement.number)),
+        )
+        .collect()
+}
+
+/// Finds solved cells whose number is still a candidate of one of their peers
+pub fn find_naked_singles(solver_state: &Sudoku<NumberSet>) -> Vec<Single> {
+    solver_state
+        .iter_with_index()
+        .filter(|(_, _, cell)| cell.is_singleton())
+        .map(|(r, c, &number)| {
+            let placement = Candidate::new(r, c, number);
+            Single {
+                kind: SingleKind::Naked,
+                placement,
+                house: None,
+                eliminations: placement_eliminations(solver_state, placement),
+            }
+        })
+        .filter(|single| !single.eliminations.is_empty())
+        .collect()
+}
+
+/// Finds numbers that can only go into one cell of a house, where placing them eliminates
+/// anything. This includes cells that have just lost all other candidates, so that a cell that
+/// is both a hidden and a naked single counts as the easier hidden single.
+pub fn find_hidden_singles(solver_state: &Sudoku<NumberSet>) -> Vec<Single> {
+    let mut found: Vec<Single> = Vec::new();
+    for house in solver_state.houses() {
+        for number in NumberSet::all().iter() {
+            let mut places = house
+                .cells()
+                .iter()
+                .filter(|&&(r, c)| solver_state.get(r, c).contains(number));
+            if let (Some(&(r, c)), None) = (places.next(), places.next()) {
+                let placement = Candidate::new(r, c, number);
+                // a cell can be a hidden single in more than one house
+                if found.iter().any(|single| single.placement == placement) {
+                    continue;
+                }
+                let eliminations = placement_eliminations(solver_state, placement);
+                if !eliminations.is_empty() {
+                    found.push(Single {
+                        kind: SingleKind::Hidden,
+                        placement,
+                        house: Some(house),
+                        eliminations,
+                    });
+                }
+            }
+        }
+    }
+    found
+}
+
+/// The grid with the numbers of its solved cells removed from their peers. Unlike
+/// `compute_exclude`, cells that get solved by this are not used in turn, so they are left for
+/// the singles finders.
+pub fn exclude_solved(grid: &Sudoku<NumberSet>) -> Sudoku<NumberSet> {
+    let mut solver_state = *grid;
+    compute_naked_singles(&mut solver_state);
+    solver_state
+}
+
+/// compute field constraints: remove the numbers of solved cells from their peers
+pub fn compute_naked_singles(solver_state: &mut Sudoku<NumberSet>) -> Vec<Single> {
+    let found = find_naked_singles(solver_state);
+    solver_state.apply_eliminations(found.iter().flat_map(|s| &s.eliminations));
+    found
+}
+
+/// compute field constraints: place numbers that can only go into one cell of a house
+pub fn compute_hidden_singles(solver_state: &mut Sudoku<NumberSet>) -> Vec<Single> {
+    let found = find_hidden_singles(solver_state);
+    solver_state.apply_eliminations(found.iter().flat_map(|s| &s.eliminations));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{Ix, Ix1, Ix2, Ix9};
+    use crate::examples::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_singles() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        *sudoku.get_mut(Ix1, Ix1) = NumberSet::N5;
+        let naked = find_naked_singles(&sudoku);
+        assert_eq!(naked.len(), 1);
+        assert_eq!(naked[0].placement, Candidate::new(Ix1, Ix1, NumberSet::N5));
+        assert_eq!(naked[0].eliminations.len(), 20);
+        assert!(find_hidden_singles(&sudoku).is_empty());
+
+        for c in Ix::all_indices().filter(|&c| c != Ix9) {
+            sudoku.eliminate(Ix2, c, NumberSet::N7);
+        }
+        let hidden = find_hidden_singles(&sudoku);
+        assert_eq!(hidden.len(), 1);
+        assert_eq!(hidden[0].house, Some(House::row(Ix2)));
+        assert_eq!(hidden[0].placement, Candidate::new(Ix2, Ix9, NumberSet::N7));
+        // the 8 other candidates of r2c9 and the 7 in column 9 and block 3
+        assert_eq!(hidden[0].eliminations.len(), 1 + 8 + 4);
+        compute_hidden_singles(&mut sudoku);
+        assert_eq!(*sudoku.get(Ix2, Ix9), NumberSet::N7);
+    }
+}
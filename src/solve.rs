@@ -1,43 +1,44 @@
-use crate::base::{NumberSet, Sudoku, compute_exclude};
+use crate::base::{compute_exclude, Board, CandidateSet, CellSelection, NumberSet, Sudoku};
+use crate::dlx::ExactCover;
 
-pub enum SolverResult {
-    Solved(Sudoku<NumberSet>),
-    Contradiction(Sudoku<NumberSet>),
+pub enum SolverResult<S = Sudoku<NumberSet>> {
+    Solved(S),
+    Contradiction(S),
 }
 
 
-pub fn brute_force(mut sudoku: Sudoku<NumberSet>) -> SolverResult {
+pub fn brute_force<S: Board>(mut sudoku: S, selection: CellSelection) -> SolverResult<S> {
     if sudoku.is_solved() {
         return SolverResult::Solved(sudoku);
     }
     if sudoku.is_invalid() {
         return SolverResult::Contradiction(sudoku);
     }
-    let first_empty_cell = sudoku.iter_with_index().find(|elem| !elem.2.is_singleton());
-    let (r, c, cell) = match first_empty_cell {
+    let (r, c) = match selection.select_cell(&sudoku) {
         None => {
             return SolverResult::Contradiction(sudoku);
         }
         Some(x) => x,
     };
-    let cell = *cell;
-    for val in NumberSet::VALUES.iter() {
-        if cell.intersects(*val) {
-            *sudoku.get_mut(r, c) = *val;
-            sudoku = match brute_force(sudoku) {
-                SolverResult::Solved(s) => {
-                    return SolverResult::Solved(s);
-                }
-                SolverResult::Contradiction(s) => s,
-            };
-            *sudoku.get_mut(r, c) = cell;
-        }
+    let cell = sudoku.cell(r, c);
+    for val in cell.singletons() {
+        *sudoku.cell_mut(r, c) = val;
+        sudoku = match brute_force(sudoku, selection) {
+            SolverResult::Solved(s) => {
+                return SolverResult::Solved(s);
+            }
+            SolverResult::Contradiction(s) => s,
+        };
+        *sudoku.cell_mut(r, c) = cell;
     }
     // If all of the previous attempts returned Contradiction, that's what we return too
     SolverResult::Contradiction(sudoku)
 }
 
-pub fn brute_force_with_exclude(mut sudoku: Sudoku<NumberSet>) -> SolverResult {
+pub fn brute_force_with_exclude<S: Board>(
+    mut sudoku: S,
+    selection: CellSelection,
+) -> SolverResult<S> {
     compute_exclude(&mut sudoku);
     if sudoku.is_solved() {
         return SolverResult::Solved(sudoku);
@@ -45,27 +46,243 @@ pub fn brute_force_with_exclude(mut sudoku: Sudoku<NumberSet>) -> SolverResult {
     if sudoku.is_invalid() {
         return SolverResult::Contradiction(sudoku);
     }
-    let first_empty_cell = sudoku.iter_with_index().find(|elem| !elem.2.is_singleton());
-    let (r, c, cell) = match first_empty_cell {
+    let (r, c) = match selection.select_cell(&sudoku) {
         None => {
             return SolverResult::Contradiction(sudoku);
         }
         Some(x) => x,
     };
-    let cell = *cell;
-    for val in NumberSet::VALUES.iter() {
-        if cell.intersects(*val) {
-            let mut sudoku_down = sudoku.clone();
-            *sudoku_down.get_mut(r, c) = *val;
-            match brute_force_with_exclude(sudoku_down) {
-                SolverResult::Solved(s) => {
-                    return SolverResult::Solved(s);
-                }
-                SolverResult::Contradiction(s) => (),
-            };
-        }
+    let cell = sudoku.cell(r, c);
+    for val in cell.singletons() {
+        let mut sudoku_down = sudoku.clone();
+        *sudoku_down.cell_mut(r, c) = val;
+        match brute_force_with_exclude(sudoku_down, selection) {
+            SolverResult::Solved(s) => {
+                return SolverResult::Solved(s);
+            }
+            SolverResult::Contradiction(_) => (),
+        };
     }
     // If all of the previous attempts returned Contradiction, that's what we return too
     SolverResult::Contradiction(sudoku)
 }
 
+/// Iterator over all solutions of a sudoku. The solutions are found lazily by a depth-first
+/// search that keeps its pending branches on an explicit stack instead of recursing.
+pub struct AllSolutions<S = Sudoku<NumberSet>> {
+    stack: Vec<S>,
+    selection: CellSelection,
+}
+
+impl<S: Board> AllSolutions<S> {
+    pub fn new(sudoku: S) -> Self {
+        Self::with_selection(sudoku, CellSelection::MinRemaining)
+    }
+
+    pub fn with_selection(sudoku: S, selection: CellSelection) -> Self {
+        Self {
+            stack: vec![sudoku],
+            selection,
+        }
+    }
+}
+
+impl<S: Board> Iterator for AllSolutions<S> {
+    type Item = S;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(mut sudoku) = self.stack.pop() {
+            compute_exclude(&mut sudoku);
+            if sudoku.is_solved() {
+                return Some(sudoku);
+            }
+            if sudoku.is_invalid() {
+                continue;
+            }
+            if let Some((r, c)) = self.selection.select_cell(&sudoku) {
+                let values: Vec<_> = sudoku.cell(r, c).singletons().collect();
+                // pushed in reverse so that the smallest number is tried first
+                for &val in values.iter().rev() {
+                    let mut sudoku_down = sudoku.clone();
+                    *sudoku_down.cell_mut(r, c) = val;
+                    self.stack.push(sudoku_down);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Counts the solutions of a sudoku, but stops counting at `limit`
+pub fn count_solutions<S: Board>(sudoku: S, limit: usize) -> usize {
+    AllSolutions::new(sudoku).take(limit).count()
+}
+
+/// Whether a sudoku has zero, one or more solutions, with the solutions found as witnesses
+pub enum Uniqueness<S = Sudoku<NumberSet>> {
+    Unsolvable,
+    Unique(S),
+    /// Two different solutions
+    Multiple(S, S),
+}
+
+impl<S> Uniqueness<S> {
+    pub fn is_unique(&self) -> bool {
+        match self {
+            Uniqueness::Unique(_) => true,
+            Uniqueness::Unsolvable | Uniqueness::Multiple(_, _) => false,
+        }
+    }
+}
+
+/// Checks if the sudoku has exactly one solution, by searching for up to two solutions
+pub fn has_unique_solution<S: Board>(sudoku: S) -> Uniqueness<S> {
+    let mut solutions = AllSolutions::new(sudoku);
+    match (solutions.next(), solutions.next()) {
+        (None, _) => Uniqueness::Unsolvable,
+        (Some(first), None) => Uniqueness::Unique(first),
+        (Some(first), Some(second)) => Uniqueness::Multiple(first, second),
+    }
+}
+
+/// Exact cover encoding: one column per cell and one per (house, number), one row per candidate
+fn exact_cover<S: Board>(sudoku: &S) -> ExactCover {
+    let layout = sudoku.layout();
+    let houses = layout.houses();
+    let n = layout.size();
+    let num_cells = n * n;
+    let mut ec = ExactCover::new(num_cells + houses.len() * n);
+    for (r, c) in layout.positions() {
+        let cell_col = r * n + c;
+        for (k, val) in sudoku.all_numbers().singletons().enumerate() {
+            if sudoku.cell(r, c) & val != S::Set::NONE {
+                let mut cols = vec![cell_col];
+                for (h, house) in houses.iter().enumerate() {
+                    if house.contains(&(r, c)) {
+                        cols.push(num_cells + h * n + k);
+                    }
+                }
+                ec.add_row(cell_col * n + k, &cols);
+            }
+        }
+    }
+    ec
+}
+
+/// Fills in the candidates selected by an exact cover of `exact_cover(sudoku)`
+fn apply_cover<S: Board>(sudoku: &mut S, rows: &[usize]) {
+    let n = sudoku.layout().size();
+    let values: Vec<_> = sudoku.all_numbers().singletons().collect();
+    for row in rows {
+        let cell = row / n;
+        *sudoku.cell_mut(cell / n, cell % n) = values[row % n];
+    }
+}
+
+/// Solves the sudoku as an exact cover problem with Dancing Links (Algorithm X).
+/// Only the candidates of each cell are considered, so a cell without candidates is a contradiction.
+pub fn dancing_links<S: Board>(mut sudoku: S) -> SolverResult<S> {
+    match exact_cover(&sudoku).first_solution() {
+        Some(rows) => {
+            apply_cover(&mut sudoku, &rows);
+            SolverResult::Solved(sudoku)
+        }
+        None => SolverResult::Contradiction(sudoku),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::Ix1;
+    use crate::examples::*;
+    use std::str::FromStr;
+
+    fn solved(result: SolverResult) -> Option<Sudoku<NumberSet>> {
+        match result {
+            SolverResult::Solved(s) => Some(s),
+            SolverResult::Contradiction(_) => None,
+        }
+    }
+
+    #[test]
+    fn test_cell_selection() {
+        let sudoku = Sudoku::from_str(SUDOKU1).unwrap();
+        let expected = Sudoku::from_str(SOLVED_SUDOKU).unwrap();
+        for &selection in CellSelection::ALL.iter() {
+            assert!(solved(brute_force(sudoku, selection)) == Some(expected));
+            assert!(solved(brute_force_with_exclude(sudoku, selection)) == Some(expected));
+        }
+        // r1c1 can only be 1, 2 or 7
+        assert_eq!(CellSelection::FirstEmpty.select(&sudoku), Some((Ix1, Ix1)));
+        assert_eq!(
+            sudoku.remaining_values(Ix1, Ix1),
+            NumberSet::N1 | NumberSet::N2 | NumberSet::N7
+        );
+        let (r, c) = CellSelection::MinRemaining.select(&sudoku).unwrap();
+        assert!(sudoku.remaining_values(r, c).len() <= 2);
+    }
+
+    #[test]
+    fn test_all_solutions() {
+        let sudoku = Sudoku::from_str(SUDOKU1).unwrap();
+        let solutions: Vec<_> = AllSolutions::new(sudoku).collect();
+        assert_eq!(solutions.len(), 1);
+        assert!(solutions[0] == Sudoku::from_str(SOLVED_SUDOKU).unwrap());
+
+        let empty = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        let solutions: Vec<_> = AllSolutions::new(empty).take(100).collect();
+        assert_eq!(solutions.len(), 100);
+        for (i, solution) in solutions.iter().enumerate() {
+            assert!(solution.is_solved());
+            assert!(solutions[..i].iter().all(|other| other != solution));
+        }
+    }
+
+    #[test]
+    fn test_count_solutions() {
+        let sudoku = Sudoku::from_str(SUDOKU3).unwrap();
+        assert_eq!(count_solutions(sudoku, 10), 1);
+        assert_eq!(count_solutions(Sudoku::from_str(EMPTY_SUDOKU).unwrap(), 10), 10);
+        assert_eq!(count_solutions(Sudoku::from_str(EMPTY_SUDOKU).unwrap(), 0), 0);
+    }
+
+    #[test]
+    fn test_has_unique_solution() {
+        let sudoku = Sudoku::from_str(SUDOKU1).unwrap();
+        match has_unique_solution(sudoku) {
+            Uniqueness::Unique(s) => assert!(s == Sudoku::from_str(SOLVED_SUDOKU).unwrap()),
+            _ => panic!("SUDOKU1 has a unique solution"),
+        }
+        match has_unique_solution(Sudoku::from_str(EMPTY_SUDOKU).unwrap()) {
+            Uniqueness::Multiple(first, second) => {
+                assert!(first.is_solved());
+                assert!(second.is_solved());
+                assert!(first != second);
+            }
+            _ => panic!("EMPTY_SUDOKU has multiple solutions"),
+        }
+        let mut sudoku = Sudoku::from_str(SUDOKU1).unwrap();
+        *sudoku.get_mut(Ix1, Ix1) = NumberSet::N8;
+        assert!(matches!(has_unique_solution(sudoku), Uniqueness::Unsolvable));
+    }
+
+    #[test]
+    fn test_dancing_links() {
+        let sudoku = Sudoku::from_str(SUDOKU1).unwrap();
+        let expected = Sudoku::from_str(SOLVED_SUDOKU).unwrap();
+        assert!(solved(dancing_links(sudoku)) == Some(expected));
+        for input in [SUDOKU2, SUDOKU3, EMPTY_SUDOKU].iter() {
+            let solution = solved(dancing_links(Sudoku::from_str(input).unwrap())).unwrap();
+            assert!(solution.is_solved());
+        }
+    }
+
+    #[test]
+    fn test_dancing_links_contradiction() {
+        let mut sudoku = Sudoku::from_str(SUDOKU1).unwrap();
+        // a second 8 in the first row
+        *sudoku.get_mut(Ix1, Ix1) = NumberSet::N8;
+        assert!(solved(dancing_links(sudoku)).is_none());
+    }
+}
@@ -0,0 +1,827 @@
+//! Solving strategies and pipelines of them
+//!
+//! A `Strategy` finds and applies one deduction at a time and reports it as a `Step`. A
+//! `Pipeline` runs an ordered list of strategies until none of them finds anything: after
+//! every step it starts over with the first one, so cheap strategies are always preferred.
+//!
+//! `Pipeline::standard` contains all techniques of this crate, ordered by difficulty. Custom
+//! pipelines can leave out expensive techniques or add their own strategies.
+//!
+//! A pipeline is a `base::Propagation`, so `compute_solve1` and `compute_solve_tree` run it in
+//! place of the singles and return its steps as their trace.
+
+use crate::als::{find_als_xy_wings, find_als_xz, find_death_blossoms, AlsDeduction, AlsKind};
+use crate::base::{Board, Candidate, Elimination, House, Ix, NumberSet, Propagation, Sudoku};
+use crate::chains::{
+    find_aics, find_nice_loops, find_x_chains, find_xy_chains, Chain, ChainKind, ChainNode,
+};
+use crate::coloring::{find_multi_coloring, find_simple_coloring, Coloring, ColoringKind};
+use crate::fish::{find_fish, Fish};
+use crate::forcing::{
+    find_cell_forcing_chains, find_nishio, find_unit_forcing_chains, ForcingChain, ForcingKind,
+};
+use crate::intersections::{find_claiming, find_pointing, IntersectionKind, LockedCandidates};
+use crate::singles::{find_hidden_singles, find_naked_singles, Single, SingleKind};
+use crate::subsets::{find_hidden_subsets, find_naked_subsets, Subset, SubsetKind};
+use crate::uniqueness::{
+    find_avoidable_rectangles, find_bug_plus_one, find_unique_rectangles, UniquenessPattern,
+};
+use crate::wings::{find_w_wings, find_xy_wings, find_xyz_wings, Wing, WingKind};
+
+/// Difficulty weight of a guess, harder than any technique
+pub const GUESS_DIFFICULTY: f32 = 10.0;
+
+/// The strategies of `Pipeline::subsets`
+const SUBSET_STRATEGIES: [&str; 8] = [
+    "Hidden Single",
+    "Naked Single",
+    "Naked Pair",
+    "Hidden Pair",
+    "Naked Triple",
+    "Hidden Triple",
+    "Naked Quad",
+    "Hidden Quad",
+];
+
+/// Options for the logical solver
+#[derive(Copy, Clone, Default, PartialEq)]
+pub struct SolveOptions {
+    /// Use techniques that are only valid if the puzzle has a unique solution
+    pub assume_uniqueness: bool,
+    /// The puzzle as it was given. Avoidable rectangles need to know which cells were solved
+    /// later on, so they are left out without it.
+    pub givens: Option<Sudoku<NumberSet>>,
+}
+
+/// One deduction of a strategy
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    /// Name of the pattern that was found, e.g. "Hidden Single" or "Sashimi Swordfish"
+    pub technique: String,
+    /// Difficulty weight of the strategy that found the step
+    pub difficulty: f32,
+    /// The houses the pattern is based on
+    pub houses: Vec<House>,
+    /// The cells that justify the deduction
+    pub cells: Vec<(Ix, Ix)>,
+    /// The numbers the pattern is about
+    pub numbers: NumberSet,
+    /// The candidates and links of a chain, empty for other techniques
+    pub chain: Vec<ChainNode>,
+    /// The cells that get solved by the step
+    pub placements: Vec<Candidate>,
+    pub eliminations: Vec<Elimination>,
+}
+
+impl Step {
+    pub fn new(
+        technique: &str,
+        houses: Vec<House>,
+        cells: Vec<(Ix, Ix)>,
+        eliminations: Vec<Elimination>,
+    ) -> Self {
+        Self {
+            technique: technique.to_string(),
+            difficulty: 0.0,
+            houses,
+            cells,
+            numbers: NumberSet::NONE,
+            chain: Vec::new(),
+            placements: Vec::new(),
+            eliminations,
+        }
+    }
+
+    /// Trying a candidate without a logical reason, as the backtracking solver does
+    pub fn guess(candidate: Candidate) -> Self {
+        Self {
+            technique: "Guess".to_string(),
+            difficulty: GUESS_DIFFICULTY,
+            houses: Vec::new(),
+            cells: vec![candidate.cell()],
+            numbers: candidate.number,
+            chain: Vec::new(),
+            placements: vec![candidate],
+            eliminations: Vec::new(),
+        }
+    }
+
+    /// The eliminations split into single candidates
+    pub fn eliminated_candidates(&self) -> Vec<Candidate> {
+        self.eliminations
+            .iter()
+            .flat_map(|e| {
+                e.numbers
+                    .iter()
+                    .map(move |n| Candidate::new(e.row, e.col, n))
+            })
+            .collect()
+    }
+
+    /// Applies the placements and eliminations of the step
+    pub fn apply(&self, solver_state: &mut Sudoku<NumberSet>) {
+        for placement in self.placements.iter() {
+            *solver_state.get_mut(placement.row, placement.col) = placement.number;
+        }
+        solver_state.apply_eliminations(self.eliminations.iter());
+    }
+}
+
+/// Sorted cells without duplicates
+fn unique_cells<I: IntoIterator<Item = (Ix, Ix)>>(cells: I) -> Vec<(Ix, Ix)> {
+    let mut cells: Vec<(Ix, Ix)> = cells.into_iter().collect();
+    cells.sort();
+    cells.dedup();
+    cells
+}
+
+/// What a strategy is based on
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StrategyKind {
+    /// A pattern of candidates in the grid
+    Pattern,
+    /// A deadly pattern, only valid if the puzzle has a unique solution
+    Uniqueness,
+    /// Trying candidates and following what they imply, which goes beyond patterns
+    Forcing,
+}
+
+/// A solving technique that can be part of a `Pipeline`
+pub trait Strategy {
+    fn name(&self) -> &str;
+
+    /// Difficulty weight, higher is harder
+    fn difficulty(&self) -> f32;
+
+    fn kind(&self) -> StrategyKind {
+        StrategyKind::Pattern
+    }
+
+    /// Finds one deduction, applies it and returns it. Returns None if nothing was found.
+    fn apply(&self, solver_state: &mut Sudoku<NumberSet>) -> Option<Step>;
+}
+
+/// A strategy that is based on a non-mutating finder
+#[derive(Copy, Clone)]
+pub struct Technique {
+    name: &'static str,
+    difficulty: f32,
+    kind: StrategyKind,
+    find: fn(&Sudoku<NumberSet>) -> Vec<Step>,
+}
+
+impl Technique {
+    pub fn new(
+        name: &'static str,
+        difficulty: f32,
+        find: fn(&Sudoku<NumberSet>) -> Vec<Step>,
+    ) -> Self {
+        Self {
+            name,
+            difficulty,
+            kind: StrategyKind::Pattern,
+            find,
+        }
+    }
+
+    pub fn with_kind(mut self, kind: StrategyKind) -> Self {
+        self.kind = kind;
+        self
+    }
+}
+
+impl Strategy for Technique {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn difficulty(&self) -> f32 {
+        self.difficulty
+    }
+
+    fn kind(&self) -> StrategyKind {
+        self.kind
+    }
+
+    fn apply(&self, solver_state: &mut Sudoku<NumberSet>) -> Option<Step> {
+        let mut step = (self.find)(solver_state).into_iter().next()?;
+        step.difficulty = self.difficulty;
+        step.apply(solver_state);
+        Some(step)
+    }
+}
+
+/// Avoidable rectangles, which also need the givens of the puzzle
+pub struct AvoidableRectangles {
+    givens: Sudoku<NumberSet>,
+}
+
+impl AvoidableRectangles {
+    pub fn new(givens: Sudoku<NumberSet>) -> Self {
+        Self { givens }
+    }
+}
+
+impl Strategy for AvoidableRectangles {
+    fn name(&self) -> &str {
+        "Avoidable Rectangle"
+    }
+
+    fn difficulty(&self) -> f32 {
+        4.7
+    }
+
+    fn kind(&self) -> StrategyKind {
+        StrategyKind::Uniqueness
+    }
+
+    fn apply(&self, solver_state: &mut Sudoku<NumberSet>) -> Option<Step> {
+        let found = find_avoidable_rectangles(solver_state, &self.givens);
+        let mut step = uniqueness_steps(found).into_iter().next()?;
+        step.difficulty = self.difficulty();
+        step.apply(solver_state);
+        Some(step)
+    }
+}
+
+fn single_steps(solver_state: &Sudoku<NumberSet>, found: Vec<Single>) -> Vec<Step> {
+    found
+        .into_iter()
+        .map(|single| {
+            let (r, c) = single.placement.cell();
+            let (name, houses) = match single.kind {
+                SingleKind::Naked => ("Naked Single", solver_state.houses_for_cell(r, c).collect()),
+                SingleKind::Hidden => ("Hidden Single", single.house.into_iter().collect()),
+            };
+            let mut step = Step::new(name, houses, vec![(r, c)], single.eliminations);
+            step.numbers = single.placement.number;
+            step.placements.push(single.placement);
+            step
+        })
+        .collect()
+}
+
+fn locked_steps(found: Vec<LockedCandidates>) -> Vec<Step> {
+    found
+        .into_iter()
+        .map(|locked| {
+            let name = match locked.kind {
+                IntersectionKind::Pointing => "Pointing",
+                IntersectionKind::Claiming => "Claiming",
+            };
+            let houses = vec![locked.base, locked.cover];
+            let mut step = Step::new(name, houses, locked.cells, locked.eliminations);
+            step.numbers = locked.number;
+            step
+        })
+        .collect()
+}
+
+fn subset_steps(found: Vec<Subset>) -> Vec<Step> {
+    found
+        .into_iter()
+        .map(|subset| {
+            let kind = match subset.kind {
+                SubsetKind::Naked => "Naked",
+                SubsetKind::Hidden => "Hidden",
+            };
+            let size = match subset.size() {
+                2 => "Pair",
+                3 => "Triple",
+                _ => "Quad",
+            };
+            let mut step = Step::new(
+                &format!("{} {}", kind, size),
+                vec![subset.house],
+                subset.cells,
+                subset.eliminations,
+            );
+            step.numbers = subset.numbers;
+            step
+        })
+        .collect()
+}
+
+fn fish_steps(solver_state: &Sudoku<NumberSet>, found: Vec<Fish>, finned: bool) -> Vec<Step> {
+    found
+        .into_iter()
+        .filter(|fish| fish.is_finned() == finned)
+        .map(|fish| {
+            // the candidates in the base lines, including the fins
+            let cells = unique_cells(fish.base.iter().flat_map(|base| {
+                base.cells()
+                    .iter()
+                    .cloned()
+                    .filter(|&(r, c)| solver_state.get(r, c).contains(fish.number))
+            }));
+            let houses = fish.base.iter().chain(fish.cover.iter()).cloned().collect();
+            let mut step = Step::new(&fish.name(), houses, cells, fish.eliminations);
+            step.numbers = fish.number;
+            step
+        })
+        .collect()
+}
+
+fn wing_steps(found: Vec<Wing>) -> Vec<Step> {
+    found
+        .into_iter()
+        .map(|wing| {
+            let name = match wing.kind {
+                WingKind::XY => "XY-Wing",
+                WingKind::XYZ => "XYZ-Wing",
+                WingKind::W => "W-Wing",
+            };
+            let cells = wing.pivot.iter().chain(wing.pincers.iter()).cloned();
+            let mut step = Step::new(name, Vec::new(), cells.collect(), wing.eliminations);
+            step.numbers = wing.number;
+            step
+        })
+        .collect()
+}
+
+fn coloring_steps(found: Vec<Coloring>) -> Vec<Step> {
+    found
+        .into_iter()
+        .map(|coloring| {
+            let name = match coloring.kind {
+                ColoringKind::ColorTrap => "Color Trap",
+                ColoringKind::ColorWrap => "Color Wrap",
+                ColoringKind::MultiColoring => "Multi-Coloring",
+            };
+            let cells = coloring
+                .clusters
+                .iter()
+                .flat_map(|cluster| cluster.colors.iter().flatten().cloned());
+            let mut step = Step::new(name, Vec::new(), cells.collect(), coloring.eliminations);
+            step.numbers = coloring.number;
+            step
+        })
+        .collect()
+}
+
+fn uniqueness_steps(found: Vec<UniquenessPattern>) -> Vec<Step> {
+    found
+        .into_iter()
+        .map(|pattern| {
+            let mut step = Step::new(
+                &pattern.name(),
+                Vec::new(),
+                pattern.cells.clone(),
+                pattern.eliminations,
+            );
+            step.numbers = pattern.numbers;
+            step
+        })
+        .collect()
+}
+
+fn als_steps(found: Vec<AlsDeduction>) -> Vec<Step> {
+    found
+        .into_iter()
+        .map(|deduction| {
+            let name = match deduction.kind {
+                AlsKind::XZ => "ALS-XZ",
+                AlsKind::DoublyLinkedXZ => "Doubly Linked ALS-XZ",
+                AlsKind::XYWing => "ALS-XY-Wing",
+                AlsKind::DeathBlossom => "Death Blossom",
+            };
+            let houses = deduction.sets.iter().map(|set| set.house).collect();
+            let cells = deduction.stem.into_iter().chain(
+                deduction
+                    .sets
+                    .iter()
+                    .flat_map(|set| set.cells.iter().cloned()),
+            );
+            let mut step = Step::new(name, houses, cells.collect(), deduction.eliminations);
+            step.numbers = deduction.restricted;
+            step
+        })
+        .collect()
+}
+
+fn chain_steps(found: Vec<Chain>) -> Vec<Step> {
+    found
+        .into_iter()
+        .map(|chain| {
+            let name = match chain.kind {
+                ChainKind::XChain => "X-Chain",
+                ChainKind::XYChain => "XY-Chain",
+                ChainKind::AIC => "AIC",
+                ChainKind::DiscontinuousNiceLoop => "Discontinuous Nice Loop",
+            };
+            let cells = unique_cells(chain.nodes.iter().map(|node| node.candidate.cell()));
+            let mut step = Step::new(name, Vec::new(), cells, chain.eliminations);
+            step.chain = chain.nodes;
+            step
+        })
+        .collect()
+}
+
+fn forcing_steps(found: Vec<ForcingChain>) -> Vec<Step> {
+    found
+        .into_iter()
+        .map(|chain| {
+            let name = match chain.kind {
+                ForcingKind::Nishio => "Nishio",
+                ForcingKind::Cell => "Cell Forcing Chain",
+                ForcingKind::Unit => "Unit Forcing Chain",
+            };
+            let cells = unique_cells(chain.branches.iter().map(|branch| branch.assumption.cell()));
+            let houses = chain.house.into_iter().collect();
+            let mut step = Step::new(name, houses, cells, chain.eliminations);
+            step.numbers = chain
+                .branches
+                .iter()
+                .fold(NumberSet::NONE, |numbers, branch| {
+                    numbers | branch.assumption.number
+                });
+            step
+        })
+        .collect()
+}
+
+/// An ordered list of strategies, empty by default
+#[derive(Default)]
+pub struct Pipeline {
+    strategies: Vec<Box<dyn Strategy>>,
+}
+
+impl Pipeline {
+    /// An empty pipeline
+    pub fn new() -> Self {
+        Self {
+            strategies: Vec::new(),
+        }
+    }
+
+    /// All techniques of this crate from easiest to hardest, the uniqueness based ones only if
+    /// the options allow them, avoidable rectangles only if they also have the givens
+    ///
+    /// Pass it to `compute_solve1` or `compute_solve_tree` for a full logical solve, or remove
+    /// the strategies a use case can't afford first, e.g. with `remove_kind`.
+    pub fn standard(options: SolveOptions) -> Self {
+        let mut techniques = vec![
+            Technique::new("Hidden Single", 1.5, |s| {
+                single_steps(s, find_hidden_singles(s))
+            }),
+            Technique::new("Naked Single", 2.3, |s| {
+                single_steps(s, find_naked_singles(s))
+            }),
+            Technique::new("Pointing", 2.6, |s| locked_steps(find_pointing(s))),
+            Technique::new("Claiming", 2.8, |s| locked_steps(find_claiming(s))),
+            Technique::new("Naked Pair", 3.0, |s| {
+                subset_steps(find_naked_subsets(s, 2))
+            }),
+            Technique::new("X-Wing", 3.2, |s| {
+                fish_steps(s, find_fish(s, 2, false), false)
+            }),
+            Technique::new("Hidden Pair", 3.4, |s| {
+                subset_steps(find_hidden_subsets(s, 2))
+            }),
+            Technique::new("Finned X-Wing", 3.4, |s| {
+                fish_steps(s, find_fish(s, 2, true), true)
+            }),
+            Technique::new("Naked Triple", 3.6, |s| {
+                subset_steps(find_naked_subsets(s, 3))
+            }),
+            Technique::new("Swordfish", 3.8, |s| {
+                fish_steps(s, find_fish(s, 3, false), false)
+            }),
+            Technique::new("Hidden Triple", 4.0, |s| {
+                subset_steps(find_hidden_subsets(s, 3))
+            }),
+            Technique::new("Finned Swordfish", 4.0, |s| {
+                fish_steps(s, find_fish(s, 3, true), true)
+            }),
+            Technique::new("XY-Wing", 4.2, |s| wing_steps(find_xy_wings(s))),
+            Technique::new("XYZ-Wing", 4.4, |s| wing_steps(find_xyz_wings(s))),
+            Technique::new("W-Wing", 4.4, |s| wing_steps(find_w_wings(s))),
+            Technique::new("Simple Coloring", 4.5, |s| {
+                coloring_steps(find_simple_coloring(s))
+            }),
+            Technique::new("Naked Quad", 5.0, |s| {
+                subset_steps(find_naked_subsets(s, 4))
+            }),
+            Technique::new("Multi-Coloring", 5.0, |s| {
+                coloring_steps(find_multi_coloring(s))
+            }),
+            Technique::new("Jellyfish", 5.2, |s| {
+                fish_steps(s, find_fish(s, 4, false), false)
+            }),
+            Technique::new("Hidden Quad", 5.4, |s| {
+                subset_steps(find_hidden_subsets(s, 4))
+            }),
+            Technique::new("Finned Jellyfish", 5.4, |s| {
+                fish_steps(s, find_fish(s, 4, true), true)
+            }),
+            Technique::new("ALS-XZ", 5.5, |s| als_steps(find_als_xz(s))),
+            Technique::new("ALS-XY-Wing", 6.0, |s| als_steps(find_als_xy_wings(s))),
+            Technique::new("Death Blossom", 6.5, |s| als_steps(find_death_blossoms(s))),
+            Technique::new("X-Chain", 6.6, |s| chain_steps(find_x_chains(s))),
+            Technique::new("XY-Chain", 6.6, |s| chain_steps(find_xy_chains(s))),
+            Technique::new("AIC", 7.0, |s| chain_steps(find_aics(s))),
+            Technique::new("Nice Loop", 7.0, |s| chain_steps(find_nice_loops(s))),
+            Technique::new("Nishio", 7.5, |s| forcing_steps(find_nishio(s)))
+                .with_kind(StrategyKind::Forcing),
+            Technique::new("Cell Forcing Chain", 8.2, |s| {
+                forcing_steps(find_cell_forcing_chains(s))
+            })
+            .with_kind(StrategyKind::Forcing),
+            Technique::new("Unit Forcing Chain", 8.3, |s| {
+                forcing_steps(find_unit_forcing_chains(s))
+            })
+            .with_kind(StrategyKind::Forcing),
+        ];
+        if options.assume_uniqueness {
+            techniques.push(
+                Technique::new("Unique Rectangle", 4.5, |s| {
+                    uniqueness_steps(find_unique_rectangles(s))
+                })
+                .with_kind(StrategyKind::Uniqueness),
+            );
+            techniques.push(
+                Technique::new("BUG+1", 5.6, |s| {
+                    uniqueness_steps(find_bug_plus_one(s).into_iter().collect())
+                })
+                .with_kind(StrategyKind::Uniqueness),
+            );
+        }
+        let mut strategies: Vec<Box<dyn Strategy>> = techniques
+            .into_iter()
+            .map(|technique| Box::new(technique) as Box<dyn Strategy>)
+            .collect();
+        if let (true, Some(givens)) = (options.assume_uniqueness, options.givens) {
+            strategies.push(Box::new(AvoidableRectangles::new(givens)));
+        }
+        // stable, so techniques of the same difficulty keep their order
+        strategies.sort_by(|a, b| a.difficulty().partial_cmp(&b.difficulty()).unwrap());
+        Self { strategies }
+    }
+
+    /// The singles and the naked and hidden subsets. Cheap enough to run at every node of
+    /// `compute_solve_tree`, where it needs far fewer guesses than `base::Singles`.
+    pub fn subsets() -> Self {
+        let mut pipeline = Self::standard(SolveOptions::default());
+        pipeline
+            .strategies
+            .retain(|strategy| SUBSET_STRATEGIES.contains(&strategy.name()));
+        pipeline
+    }
+
+    /// Appends a strategy, for building pipelines in one expression
+    pub fn with<S: Strategy + 'static>(mut self, strategy: S) -> Self {
+        self.push(strategy);
+        self
+    }
+
+    /// Appends a strategy
+    pub fn push<S: Strategy + 'static>(&mut self, strategy: S) {
+        self.strategies.push(Box::new(strategy));
+    }
+
+    /// Removes all strategies with the given name, returns true if there were any
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.strategies.len();
+        self.strategies.retain(|strategy| strategy.name() != name);
+        self.strategies.len() != len
+    }
+
+    /// Removes all strategies of the given kind, returns true if there were any
+    pub fn remove_kind(&mut self, kind: StrategyKind) -> bool {
+        let len = self.strategies.len();
+        self.strategies.retain(|strategy| strategy.kind() != kind);
+        self.strategies.len() != len
+    }
+
+    /// Keeps only the strategies with the given names. Returns an error and keeps everything if
+    /// one of the names isn't in the pipeline.
+    pub fn retain_names(&mut self, names: &[&str]) -> Result<(), String> {
+        if let Some(unknown) = names
+            .iter()
+            .find(|&&name| self.strategies().all(|strategy| strategy.name() != name))
+        {
+            return Err(format!("Unknown strategy: {}", unknown));
+        }
+        self.strategies
+            .retain(|strategy| names.contains(&strategy.name()));
+        Ok(())
+    }
+
+    pub fn strategies(&self) -> impl Iterator<Item = &dyn Strategy> {
+        self.strategies.iter().map(|strategy| strategy.as_ref())
+    }
+
+    /// Applies the first strategy that finds anything
+    pub fn step(&self, solver_state: &mut Sudoku<NumberSet>) -> Option<Step> {
+        self.strategies
+            .iter()
+            .find_map(|strategy| strategy.apply(solver_state))
+    }
+
+    /// Applies steps until the sudoku is solved, a contradiction shows up or no strategy finds
+    /// anything. Returns the steps in the order they were applied.
+    pub fn run(&self, solver_state: &mut Sudoku<NumberSet>) -> Vec<Step> {
+        let mut steps = Vec::new();
+        while !solver_state.is_solved() && solver_state.has_no_contradiction() {
+            match self.step(solver_state) {
+                Some(step) => steps.push(step),
+                None => break,
+            }
+        }
+        steps
+    }
+}
+
+/// The pipeline as the propagation of `compute_solve1` and `compute_solve_tree`
+impl Propagation<Sudoku<NumberSet>> for Pipeline {
+    type Step = Step;
+
+    fn propagate(&self, solver_state: &mut Sudoku<NumberSet>) -> Vec<Step> {
+        self.run(solver_state)
+    }
+
+    fn guess(&self, r: usize, c: usize, number: NumberSet) -> Step {
+        let (r, c) = (Ix::ALL_INDICES[r], Ix::ALL_INDICES[c]);
+        Step::guess(Candidate::new(r, c, number))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{compute_solve1, compute_solve_tree, CellSelection};
+    use crate::base::{Ix, Ix1, Ix2, Ix4, Ix5, Ix7, Ix8};
+    use crate::examples::*;
+    use crate::solve::{dancing_links, SolverResult};
+    use std::str::FromStr;
+
+    /// Removes the 9 from the first unsolved cell of row 1
+    struct NoNines;
+
+    impl Strategy for NoNines {
+        fn name(&self) -> &str {
+            "No Nines"
+        }
+
+        fn difficulty(&self) -> f32 {
+            10.0
+        }
+
+        fn apply(&self, solver_state: &mut Sudoku<NumberSet>) -> Option<Step> {
+            let c = Ix::all_indices().find(|&c| {
+                let cell = *solver_state.get(Ix1, c);
+                cell.len() > 1 && cell.contains(NumberSet::N9)
+            })?;
+            let elimination = solver_state.eliminate(Ix1, c, NumberSet::N9)?;
+            Some(Step::new(
+                self.name(),
+                vec![House::row(Ix1)],
+                vec![(Ix1, c)],
+                vec![elimination],
+            ))
+        }
+    }
+
+    #[test]
+    fn test_standard_pipeline() {
+        let mut sudoku = Sudoku::from_str(SUDOKU1).unwrap();
+        let solution = match dancing_links(sudoku) {
+            SolverResult::Solved(solution) => solution,
+            _ => panic!("no solution"),
+        };
+        let pipeline = Pipeline::standard(SolveOptions::default());
+        assert_eq!(
+            pipeline.strategies().next().unwrap().name(),
+            "Hidden Single"
+        );
+        let steps = pipeline.run(&mut sudoku);
+        assert!(sudoku.is_solved());
+        assert!(sudoku == solution);
+        assert!(steps.iter().all(|step| step.difficulty > 0.0));
+    }
+
+    #[test]
+    fn test_custom_pipeline() {
+        let mut pipeline = Pipeline::new().with(NoNines);
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        let steps = pipeline.run(&mut sudoku);
+        assert_eq!(steps.len(), 9);
+        assert!(steps.iter().all(|step| step.technique == "No Nines"));
+        // row 1 has no place left for the 9, which is a contradiction
+        assert!(pipeline.step(&mut sudoku).is_none());
+        assert!(sudoku.row(Ix1).all(|cell| !cell.contains(NumberSet::N9)));
+
+        assert!(pipeline.retain_names(&["No Nine"]).is_err());
+        assert_eq!(pipeline.strategies().count(), 1);
+        assert!(pipeline.retain_names(&["No Nines"]).is_ok());
+        assert!(pipeline.remove("No Nines"));
+        assert!(!pipeline.remove("No Nines"));
+        assert_eq!(pipeline.strategies().count(), 0);
+    }
+
+    #[test]
+    fn test_uniqueness_option() {
+        let names = |options| -> Vec<String> {
+            Pipeline::standard(options)
+                .strategies()
+                .map(|strategy| strategy.name().to_string())
+                .collect()
+        };
+        let strict = names(SolveOptions::default());
+        let unique = names(SolveOptions {
+            assume_uniqueness: true,
+            givens: None,
+        });
+        assert!(!strict.contains(&"BUG+1".to_string()));
+        assert_eq!(unique.len(), strict.len() + 2);
+        let ur = unique.iter().position(|n| n == "Unique Rectangle").unwrap();
+        assert_eq!(unique[ur - 1], "Simple Coloring");
+
+        let with_givens = names(SolveOptions {
+            assume_uniqueness: true,
+            givens: Some(Sudoku::from_str(SUDOKU1).unwrap()),
+        });
+        assert_eq!(with_givens.len(), strict.len() + 3);
+        let ar = with_givens
+            .iter()
+            .position(|n| n == "Avoidable Rectangle")
+            .unwrap();
+        assert_eq!(with_givens[ar - 1], "Unique Rectangle");
+    }
+
+    #[test]
+    fn test_remove_kind() {
+        let options = SolveOptions {
+            assume_uniqueness: true,
+            givens: Some(Sudoku::from_str(SUDOKU1).unwrap()),
+        };
+        let mut pipeline = Pipeline::standard(options);
+        let len = pipeline.strategies().count();
+        assert!(pipeline.remove_kind(StrategyKind::Forcing));
+        assert!(pipeline.remove_kind(StrategyKind::Uniqueness));
+        assert!(!pipeline.remove_kind(StrategyKind::Uniqueness));
+        assert_eq!(pipeline.strategies().count(), len - 6);
+        assert!(pipeline
+            .strategies()
+            .all(|strategy| strategy.kind() == StrategyKind::Pattern));
+    }
+
+    #[test]
+    fn test_avoidable_rectangles() {
+        let puzzle = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        let mut sudoku = puzzle;
+        *sudoku.get_mut(Ix1, Ix1) = NumberSet::N1;
+        *sudoku.get_mut(Ix1, Ix4) = NumberSet::N2;
+        *sudoku.get_mut(Ix2, Ix1) = NumberSet::N2 | NumberSet::N5;
+        *sudoku.get_mut(Ix2, Ix4) = NumberSet::N1 | NumberSet::N5;
+        let step = AvoidableRectangles::new(puzzle).apply(&mut sudoku).unwrap();
+        assert_eq!(step.technique, "Avoidable Rectangle Type 2");
+        assert_eq!(step.difficulty, 4.7);
+        assert!(!sudoku.get(Ix2, Ix2).contains(NumberSet::N5));
+        // nothing can be avoided if the solved corners were given
+        assert!(AvoidableRectangles::new(sudoku)
+            .apply(&mut sudoku)
+            .is_none());
+    }
+
+    #[test]
+    fn test_solve_with() {
+        let puzzle = Sudoku::from_str(crate::examples::SUDOKU1).unwrap();
+        let pipeline = Pipeline::standard(SolveOptions::default());
+        let mut solver_state = puzzle;
+        let (won, lost, steps) = compute_solve1(&mut solver_state, &pipeline);
+        assert!(won);
+        assert!(!lost);
+        // replaying the trace gives the same result
+        let mut replay = puzzle;
+        steps.iter().for_each(|step| step.apply(&mut replay));
+        assert!(replay == solver_state);
+        assert!(steps.iter().all(|step| !step.cells.is_empty()));
+
+        // without the 7s and 9s of this rectangle, there are two solutions
+        let mut solver_state = Sudoku::from_str(crate::examples::SOLVED_SUDOKU).unwrap();
+        for &(r, c) in [(Ix2, Ix7), (Ix2, Ix8), (Ix5, Ix7), (Ix5, Ix8)].iter() {
+            *solver_state.get_mut(r, c) = NumberSet::all();
+        }
+        let (won, _, steps) =
+            compute_solve_tree(&mut solver_state, &pipeline, CellSelection::FirstEmpty);
+        assert!(won);
+        let guesses: Vec<&Step> = steps.iter().filter(|s| s.technique == "Guess").collect();
+        assert_eq!(
+            guesses,
+            vec![&Step::guess(Candidate::new(Ix2, Ix7, NumberSet::N7))]
+        );
+
+        // the solvers run the pipeline they are given, an empty one finds nothing
+        let mut solver_state = puzzle;
+        let (won, lost, steps) = compute_solve1(&mut solver_state, &Pipeline::new());
+        assert!(!won && !lost && steps.is_empty());
+        let (won, _, steps) = compute_solve_tree(
+            &mut solver_state,
+            &Pipeline::new(),
+            CellSelection::FirstEmpty,
+        );
+        assert!(won);
+        assert!(steps.iter().all(|step| step.technique == "Guess"));
+    }
+}
@@ -0,0 +1,251 @@
+//! Naked and hidden subsets (pairs, triples and quads)
+//!
+//! A naked subset are n cells of a house that together have only n candidates: these numbers
+//! must go into these cells, so they can be removed from the rest of the house.
+//! A hidden subset are n numbers that can only go into the same n cells of a house: these cells
+//! can't contain anything else.
+//!
+//! `strategy::Pipeline::subsets` runs them together with the singles as the propagation of
+//! `compute_solve1` and `compute_solve_tree`, so the search has to guess less often.
+
+use crate::base::{combinations, Elimination, House, Ix, NumberSet, Sudoku};
+
+/// Sizes of the subsets that are searched for, from pairs to quads
+pub const SUBSET_SIZES: [usize; 3] = [2, 3, 4];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SubsetKind {
+    Naked,
+    Hidden,
+}
+
+/// A naked or hidden subset in a house and the candidates it eliminates
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Subset {
+    pub kind: SubsetKind,
+    pub house: House,
+    /// The cells the numbers are locked into
+    pub cells: Vec<(Ix, Ix)>,
+    /// The numbers locked into the cells
+    pub numbers: NumberSet,
+    pub eliminations: Vec<Elimination>,
+}
+
+impl Subset {
+    pub fn size(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+/// Finds all naked subsets of the given size that eliminate at least one candidate
+pub fn find_naked_subsets(solver_state: &Sudoku<NumberSet>, size: usize) -> Vec<Subset> {
+    let mut subsets = Vec::new();
+    for house in solver_state.houses() {
+        let unsolved: Vec<(Ix, Ix)> = house
+            .cells()
+            .iter()
+            .cloned()
+            .filter(|&(r, c)| (2..=size).contains(&solver_state.get(r, c).len()))
+            .collect();
+        for cells in combinations(&unsolved, size) {
+            let numbers = cells.iter().fold(NumberSet::empty(), |acc, &(r, c)| {
+                acc | *solver_state.get(r, c)
+            });
+            if numbers.len() != size {
+                continue;
+            }
+            let eliminations: Vec<Elimination> = house
+                .cells()
+                .iter()
+                .filter(|cell| !cells.contains(cell))
+                .filter_map(|&(r, c)| solver_state.elimination(r, c, numbers))
+                .collect();
+            if !eliminations.is_empty() {
+                subsets.push(Subset {
+                    kind: SubsetKind::Naked,
+                    house,
+                    cells,
+                    numbers,
+                    eliminations,
+                });
+            }
+        }
+    }
+    subsets
+}
+
+/// Finds all hidden subsets of the given size that eliminate at least one candidate
+pub fn find_hidden_subsets(solver_state: &Sudoku<NumberSet>, size: usize) -> Vec<Subset> {
+    let mut subsets = Vec::new();
+    for house in solver_state.houses() {
+        let placed = solver_state
+            .house(house)
+            .filter(|cell| cell.is_singleton())
+            .fold(NumberSet::empty(), |acc, cell| acc | *cell);
+        // the unsolved cells in which a number can go
+        let positions = |n: NumberSet| -> Vec<(Ix, Ix)> {
+            house
+                .cells()
+                .iter()
+                .cloned()
+                .filter(|&(r, c)| {
+                    let cell = *solver_state.get(r, c);
+                    !cell.is_singleton() && cell.contains(n)
+                })
+                .collect()
+        };
+        let open: Vec<NumberSet> = (NumberSet::all() - placed)
+            .iter()
+            .filter(|&n| (1..=size).contains(&positions(n).len()))
+            .collect();
+        for numbers in combinations(&open, size) {
+            let mut cells: Vec<(Ix, Ix)> = Vec::new();
+            for &n in numbers.iter() {
+                for cell in positions(n) {
+                    if !cells.contains(&cell) {
+                        cells.push(cell);
+                    }
+                }
+            }
+            if cells.len() != size {
+                continue;
+            }
+            cells.sort();
+            let numbers = numbers.iter().fold(NumberSet::empty(), |acc, &n| acc | n);
+            let eliminations: Vec<Elimination> = cells
+                .iter()
+                .filter_map(|&(r, c)| solver_state.elimination(r, c, !numbers))
+                .collect();
+            if !eliminations.is_empty() {
+                subsets.push(Subset {
+                    kind: SubsetKind::Hidden,
+                    house,
+                    cells,
+                    numbers,
+                    eliminations,
+                });
+            }
+        }
+    }
+    subsets
+}
+
+/// compute field constraints: remove candidates using naked subsets
+///
+/// Pairs are tried first, then triples, then quads. All subsets of the smallest size that
+/// has any are applied and returned.
+pub fn compute_naked_subsets(solver_state: &mut Sudoku<NumberSet>) -> Vec<Subset> {
+    for &size in SUBSET_SIZES.iter() {
+        let subsets = find_naked_subsets(solver_state, size);
+        if !subsets.is_empty() {
+            solver_state.apply_eliminations(subsets.iter().flat_map(|s| &s.eliminations));
+            return subsets;
+        }
+    }
+    Vec::new()
+}
+
+/// compute field constraints: remove candidates using hidden subsets
+///
+/// Pairs are tried first, then triples, then quads. All subsets of the smallest size that
+/// has any are applied and returned.
+pub fn compute_hidden_subsets(solver_state: &mut Sudoku<NumberSet>) -> Vec<Subset> {
+    for &size in SUBSET_SIZES.iter() {
+        let subsets = find_hidden_subsets(solver_state, size);
+        if !subsets.is_empty() {
+            solver_state.apply_eliminations(subsets.iter().flat_map(|s| &s.eliminations));
+            return subsets;
+        }
+    }
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{compute_solve1, compute_solve_tree, CellSelection, Singles};
+    use crate::base::{Ix1, Ix2, Ix3, RegionType};
+    use crate::examples::*;
+    use crate::solve::{dancing_links, SolverResult};
+    use crate::strategy::{Pipeline, SolveOptions};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_naked_pair() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        *sudoku.get_mut(Ix1, Ix1) = NumberSet::N1 | NumberSet::N2;
+        *sudoku.get_mut(Ix1, Ix2) = NumberSet::N1 | NumberSet::N2;
+        let subsets = find_naked_subsets(&sudoku, 2);
+        assert_eq!(subsets.len(), 2);
+        let row = subsets
+            .iter()
+            .find(|s| s.house.kind() == RegionType::Row)
+            .unwrap();
+        assert_eq!(row.cells, vec![(Ix1, Ix1), (Ix1, Ix2)]);
+        assert_eq!(row.numbers, NumberSet::N1 | NumberSet::N2);
+        assert_eq!(row.eliminations.len(), 7);
+
+        let applied = compute_naked_subsets(&mut sudoku);
+        assert_eq!(applied, subsets);
+        assert_eq!(sudoku.get(Ix1, Ix3).len(), 7);
+        assert_eq!(sudoku.get(Ix3, Ix3).len(), 7);
+        assert!(find_naked_subsets(&sudoku, 2).is_empty());
+    }
+
+    #[test]
+    fn test_hidden_pair() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        for c in Ix::all_indices().skip(2) {
+            sudoku.eliminate(Ix1, c, NumberSet::N1 | NumberSet::N2);
+        }
+        let subsets = find_hidden_subsets(&sudoku, 2);
+        assert_eq!(subsets.len(), 1);
+        assert_eq!(subsets[0].kind, SubsetKind::Hidden);
+        assert_eq!(subsets[0].house, House::row(Ix1));
+        assert_eq!(subsets[0].cells, vec![(Ix1, Ix1), (Ix1, Ix2)]);
+
+        compute_hidden_subsets(&mut sudoku);
+        assert_eq!(*sudoku.get(Ix1, Ix1), NumberSet::N1 | NumberSet::N2);
+        assert_eq!(*sudoku.get(Ix1, Ix2), NumberSet::N1 | NumberSet::N2);
+    }
+
+    #[test]
+    fn test_subsets_keep_solution() {
+        for input in [SUDOKU2, SUDOKU3].iter() {
+            let mut sudoku = Sudoku::from_str(input).unwrap();
+            let solution = match dancing_links(sudoku) {
+                SolverResult::Solved(s) => s,
+                SolverResult::Contradiction(_) => panic!("example has a solution"),
+            };
+            compute_solve1(&mut sudoku, &Pipeline::standard(SolveOptions::default()));
+            assert!(sudoku.is_consistent_with(&solution));
+        }
+    }
+
+    #[test]
+    fn test_fewer_guesses() {
+        let guesses = |input: &str| {
+            let mut singles = Sudoku::from_str(input).unwrap();
+            let (won, _, steps) =
+                compute_solve_tree(&mut singles, &Singles, CellSelection::FirstEmpty);
+            assert!(won);
+            let mut subsets = Sudoku::from_str(input).unwrap();
+            let pipeline = Pipeline::subsets();
+            let (won, _, subset_steps) =
+                compute_solve_tree(&mut subsets, &pipeline, CellSelection::FirstEmpty);
+            assert!(won);
+            assert!(subsets == singles);
+            (
+                steps.iter().filter(|step| step.guess).count(),
+                subset_steps
+                    .iter()
+                    .filter(|step| step.technique == "Guess")
+                    .count(),
+            )
+        };
+        // the subsets solve SUDOKU2 without guessing, the singles alone need 7 guesses
+        assert_eq!(guesses(SUDOKU2), (7, 0));
+        let (singles, subsets) = guesses(SUDOKU3);
+        assert!(subsets < singles);
+    }
+}
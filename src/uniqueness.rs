@@ -0,0 +1,669 @@
+//! Uniqueness techniques: Unique Rectangles, hidden and avoidable rectangles and BUG+1
+//!
+//! A deadly pattern is a set of unsolved cells whose numbers could be swapped without breaking
+//! any constraint, e.g. four cells in two rows, two columns and two blocks that could all only
+//! be a or b. If the puzzle has a unique solution, the deadly pattern can't happen, so at least
+//! one of its cells has to be something else.
+//!
+//! All of this is unsound for puzzles with more than one solution, so none of it is used
+//! unless uniqueness is explicitly assumed, see `SolveOptions`.
+
+use crate::base::{combinations, Elimination, House, Ix, NumberSet, RegionType, Sudoku};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum UniquenessKind {
+    /// Unique Rectangle of type 1 to 6
+    UniqueRectangle(u8),
+    HiddenRectangle,
+    /// Avoidable Rectangle of type 1 or 2
+    AvoidableRectangle(u8),
+    BugPlusOne,
+}
+
+/// A deadly pattern that has to be avoided and the candidates this eliminates
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UniquenessPattern {
+    pub kind: UniquenessKind,
+    /// The corners of a rectangle, or the cell that breaks the BUG
+    pub cells: Vec<(Ix, Ix)>,
+    /// The two numbers of a rectangle, or the number that breaks the BUG
+    pub numbers: NumberSet,
+    pub eliminations: Vec<Elimination>,
+}
+
+impl UniquenessPattern {
+    /// Name of the pattern, e.g. "Unique Rectangle Type 2" or "BUG+1"
+    pub fn name(&self) -> String {
+        match self.kind {
+            UniquenessKind::UniqueRectangle(t) => format!("Unique Rectangle Type {}", t),
+            UniquenessKind::HiddenRectangle => "Hidden Rectangle".to_string(),
+            UniquenessKind::AvoidableRectangle(t) => format!("Avoidable Rectangle Type {}", t),
+            UniquenessKind::BugPlusOne => "BUG+1".to_string(),
+        }
+    }
+}
+
+/// All rectangles that span two rows, two columns and exactly two blocks, corners in the
+/// order top left, top right, bottom left, bottom right
+///
+/// The blocks and extra houses come from the layout of the sudoku, so the blocks may be
+/// jigsaw regions. The numbers of the corners can only be swapped if each of these houses
+/// holds no corner, or two corners in one row or column.
+fn rectangles(solver_state: &Sudoku<NumberSet>) -> Vec<[(Ix, Ix); 4]> {
+    let pairs = combinations(&Ix::ALL_INDICES, 2);
+    let blocks: Vec<House> = solver_state
+        .houses()
+        .filter(|house| house.kind() != RegionType::Row && house.kind() != RegionType::Col)
+        .collect();
+    let mut found = Vec::new();
+    for rows in pairs.iter() {
+        for cols in pairs.iter() {
+            let corners = [
+                (rows[0], cols[0]),
+                (rows[0], cols[1]),
+                (rows[1], cols[0]),
+                (rows[1], cols[1]),
+            ];
+            let swappable = blocks.iter().all(|block| {
+                let inside: Vec<(Ix, Ix)> = corners
+                    .iter()
+                    .cloned()
+                    .filter(|&(r, c)| block.contains(r, c))
+                    .collect();
+                match inside.len() {
+                    0 => true,
+                    2 => inside[0].0 == inside[1].0 || inside[0].1 == inside[1].1,
+                    _ => false,
+                }
+            });
+            if swappable {
+                found.push(corners);
+            }
+        }
+    }
+    found
+}
+
+/// The houses that contain both cells
+fn common_houses(solver_state: &Sudoku<NumberSet>, a: (Ix, Ix), b: (Ix, Ix)) -> Vec<House> {
+    solver_state
+        .houses_for_cell(a.0, a.1)
+        .filter(|house| house.contains(b.0, b.1))
+        .collect()
+}
+
+/// Eliminations of the numbers in all cells that see every one of the given cells
+fn eliminations_seeing(
+    solver_state: &Sudoku<NumberSet>,
+    cells: &[(Ix, Ix)],
+    numbers: NumberSet,
+) -> Vec<Elimination> {
+    solver_state
+        .iter_with_index()
+        .filter(|&(r, c, _)| cells.iter().all(|&cell| solver_state.sees((r, c), cell)))
+        .filter_map(|(r, c, _)| solver_state.elimination(r, c, numbers))
+        .collect()
+}
+
+/// Returns true if the number can only go into the given cells within the house
+fn confined_to(
+    solver_state: &Sudoku<NumberSet>,
+    house: House,
+    number: NumberSet,
+    cells: &[(Ix, Ix)],
+) -> bool {
+    house
+        .cells()
+        .iter()
+        .filter(|&&(r, c)| solver_state.get(r, c).contains(number))
+        .all(|cell| cells.contains(cell))
+}
+
+/// Finds Unique Rectangles of types 1 to 6 and hidden rectangles
+pub fn find_unique_rectangles(solver_state: &Sudoku<NumberSet>) -> Vec<UniquenessPattern> {
+    let mut found = Vec::new();
+    for corners in rectangles(solver_state) {
+        let cands: Vec<NumberSet> = corners
+            .iter()
+            .map(|&(r, c)| *solver_state.get(r, c))
+            .collect();
+        let common = cands.iter().fold(NumberSet::all(), |acc, &cell| acc & cell);
+        for pair in combinations(&common.iter().collect::<Vec<_>>(), 2) {
+            let ab = pair[0] | pair[1];
+            find_rectangle(solver_state, corners, &cands, ab, &mut found);
+        }
+    }
+    found
+}
+
+fn find_rectangle(
+    solver_state: &Sudoku<NumberSet>,
+    corners: [(Ix, Ix); 4],
+    cands: &[NumberSet],
+    ab: NumberSet,
+    found: &mut Vec<UniquenessPattern>,
+) {
+    let mut push = |kind, eliminations: Vec<Elimination>| {
+        if !eliminations.is_empty() {
+            found.push(UniquenessPattern {
+                kind,
+                cells: corners.to_vec(),
+                numbers: ab,
+                eliminations,
+            });
+        }
+    };
+    // corners with candidates besides a and b
+    let roof: Vec<usize> = (0..4).filter(|&i| cands[i] != ab).collect();
+    let extras = roof
+        .iter()
+        .fold(NumberSet::empty(), |acc, &i| acc | (cands[i] - ab));
+    let roof_cells: Vec<(Ix, Ix)> = roof.iter().map(|&i| corners[i]).collect();
+    // corners 0 and 3 as well as 1 and 2 are diagonal
+    let adjacent = roof.len() == 2 && roof[0] + roof[1] != 3;
+
+    if roof.len() == 1 {
+        let (r, c) = roof_cells[0];
+        push(
+            UniquenessKind::UniqueRectangle(1),
+            solver_state.elimination(r, c, ab).into_iter().collect(),
+        );
+    }
+
+    let single_extra = extras.is_singleton() && roof.iter().all(|&i| cands[i] - ab == extras);
+    if roof.len() >= 2 && single_extra {
+        // one of the roof cells has to be the extra number
+        let kind = if adjacent {
+            UniquenessKind::UniqueRectangle(2)
+        } else {
+            UniquenessKind::UniqueRectangle(5)
+        };
+        push(kind, eliminations_seeing(solver_state, &roof_cells, extras));
+    }
+
+    if adjacent {
+        let (r1, r2) = (roof_cells[0], roof_cells[1]);
+        for house in common_houses(solver_state, r1, r2) {
+            // type 3: the extras of the roof act like one more cell of a naked subset
+            if extras.len() >= 2 {
+                let others: Vec<(Ix, Ix)> = house
+                    .cells()
+                    .iter()
+                    .cloned()
+                    .filter(|&(r, c)| !roof_cells.contains(&(r, c)))
+                    .filter(|&(r, c)| solver_state.get(r, c).len() > 1)
+                    .collect();
+                for size in 1..=3 {
+                    for subset in combinations(&others, size) {
+                        let numbers = subset
+                            .iter()
+                            .fold(extras, |acc, &(r, c)| acc | *solver_state.get(r, c));
+                        if numbers.len() != size + 1 {
+                            continue;
+                        }
+                        push(
+                            UniquenessKind::UniqueRectangle(3),
+                            others
+                                .iter()
+                                .filter(|cell| !subset.contains(cell))
+                                .filter_map(|&(r, c)| solver_state.elimination(r, c, numbers))
+                                .collect(),
+                        );
+                    }
+                }
+            }
+            // type 4: if a has to be in one of the roof cells, b can't be in either of them
+            for a in ab.iter() {
+                if confined_to(solver_state, house, a, &roof_cells) {
+                    push(
+                        UniquenessKind::UniqueRectangle(4),
+                        roof_cells
+                            .iter()
+                            .filter_map(|&(r, c)| solver_state.elimination(r, c, ab - a))
+                            .collect(),
+                    );
+                }
+            }
+        }
+    }
+
+    // type 6: the floor cells are diagonal and a forms an X-Wing on the rectangle
+    if roof.len() == 2 && !adjacent {
+        for a in ab.iter() {
+            let lines = |kind| {
+                [corners[0], corners[3]]
+                    .iter()
+                    .map(|&(r, c)| House::new(kind, if kind == RegionType::Row { r } else { c }))
+                    .all(|line| confined_to(solver_state, line, a, &corners))
+            };
+            if lines(RegionType::Row) || lines(RegionType::Col) {
+                push(
+                    UniquenessKind::UniqueRectangle(6),
+                    roof_cells
+                        .iter()
+                        .filter_map(|&(r, c)| solver_state.elimination(r, c, a))
+                        .collect(),
+                );
+            }
+        }
+    }
+
+    // hidden rectangle: if a is confined to the rectangle in both lines through the corner
+    // opposite of a bivalue corner, that corner can't be b
+    if roof.len() >= 2 {
+        for floor in (0..4).filter(|&i| cands[i] == ab) {
+            let (r, c) = corners[3 - floor];
+            for a in ab.iter() {
+                if confined_to(solver_state, House::row(r), a, &corners)
+                    && confined_to(solver_state, House::col(c), a, &corners)
+                {
+                    push(
+                        UniquenessKind::HiddenRectangle,
+                        solver_state.elimination(r, c, ab - a).into_iter().collect(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Finds avoidable rectangles: rectangles in which some corners were solved, but none of them
+/// was given in `puzzle`, so the solved corners could be swapped as well
+pub fn find_avoidable_rectangles(
+    solver_state: &Sudoku<NumberSet>,
+    puzzle: &Sudoku<NumberSet>,
+) -> Vec<UniquenessPattern> {
+    let mut found = Vec::new();
+    let solved_not_given = |(r, c): (Ix, Ix)| {
+        solver_state.get(r, c).is_singleton() && !puzzle.get(r, c).is_singleton()
+    };
+    for corners in rectangles(solver_state) {
+        // try each corner as the unsolved one of type 1, or each diagonal as the unsolved
+        // pair of type 2
+        for d in 0..4 {
+            let (a_cell, b_cells) = (corners[3 - d], [corners[d ^ 1], corners[d ^ 2]]);
+            if !solved_not_given(a_cell) || !b_cells.iter().all(|&cell| solved_not_given(cell)) {
+                continue;
+            }
+            let a = *solver_state.get(a_cell.0, a_cell.1);
+            let b = *solver_state.get(b_cells[0].0, b_cells[0].1);
+            if a == b || *solver_state.get(b_cells[1].0, b_cells[1].1) != b {
+                continue;
+            }
+            let (r, c) = corners[d];
+            if let Some(elimination) = solver_state.elimination(r, c, a) {
+                if solver_state.get(r, c).len() > 1 {
+                    found.push(UniquenessPattern {
+                        kind: UniquenessKind::AvoidableRectangle(1),
+                        cells: corners.to_vec(),
+                        numbers: a | b,
+                        eliminations: vec![elimination],
+                    });
+                }
+            }
+        }
+        // type 2: two adjacent corners are solved as a and b, so the other two would have to
+        // be b and a, and one of them has to be something else
+        for &(i, j) in [(0, 1), (2, 3), (0, 2), (1, 3)].iter() {
+            let k = 3 - (i ^ j);
+            let (s1, s2) = (corners[i], corners[j]);
+            let (u1, u2) = (corners[i ^ k], corners[j ^ k]);
+            if !solved_not_given(s1) || !solved_not_given(s2) {
+                continue;
+            }
+            let (a, b) = (*solver_state.get(s1.0, s1.1), *solver_state.get(s2.0, s2.1));
+            let (cand1, cand2) = (*solver_state.get(u1.0, u1.1), *solver_state.get(u2.0, u2.1));
+            let (x1, x2) = (cand1 - b, cand2 - a);
+            if a == b
+                || cand1.len() != 2
+                || cand2.len() != 2
+                || !cand1.contains(b)
+                || !cand2.contains(a)
+                || x1 != x2
+            {
+                continue;
+            }
+            let eliminations = eliminations_seeing(solver_state, &[u1, u2], x1);
+            if !eliminations.is_empty() {
+                found.push(UniquenessPattern {
+                    kind: UniquenessKind::AvoidableRectangle(2),
+                    cells: corners.to_vec(),
+                    numbers: a | b,
+                    eliminations,
+                });
+            }
+        }
+    }
+    found
+}
+
+/// Finds a BUG+1: all unsolved cells but one have two candidates, and every number appears
+/// twice in each house, except for one number in the houses of the cell with three
+/// candidates. That number has to be placed there, or the grid would have two solutions.
+pub fn find_bug_plus_one(solver_state: &Sudoku<NumberSet>) -> Option<UniquenessPattern> {
+    let mut tri = None;
+    for (r, c, cell) in solver_state.iter_with_index() {
+        match cell.len() {
+            0 => return None,
+            1 | 2 => {}
+            3 if tri.is_none() => tri = Some((r, c)),
+            _ => return None,
+        }
+    }
+    let (r, c) = tri?;
+    let count = |house: House, number: NumberSet| {
+        house
+            .cells()
+            .iter()
+            .filter(|&&(r, c)| {
+                let cell = *solver_state.get(r, c);
+                cell.len() > 1 && cell.contains(number)
+            })
+            .count()
+    };
+    let houses: Vec<House> = solver_state.houses_for_cell(r, c).collect();
+    let bug = solver_state
+        .get(r, c)
+        .iter()
+        .find(|&n| houses.iter().all(|&house| count(house, n) == 3))?;
+    let consistent = solver_state.houses().all(|house| {
+        NumberSet::all().iter().all(|n| {
+            let expected = if n == bug && houses.contains(&house) {
+                3
+            } else {
+                2
+            };
+            let k = count(house, n);
+            k == 0 || k == expected
+        })
+    });
+    if !consistent {
+        return None;
+    }
+    let others = *solver_state.get(r, c) - bug;
+    Some(UniquenessPattern {
+        kind: UniquenessKind::BugPlusOne,
+        cells: vec![(r, c)],
+        numbers: bug,
+        eliminations: solver_state.elimination(r, c, others).into_iter().collect(),
+    })
+}
+
+/// compute field constraints: remove candidates using unique and hidden rectangles
+///
+/// Only valid for puzzles with a unique solution.
+pub fn compute_unique_rectangles(solver_state: &mut Sudoku<NumberSet>) -> Vec<UniquenessPattern> {
+    let found = find_unique_rectangles(solver_state);
+    solver_state.apply_eliminations(found.iter().flat_map(|p| &p.eliminations));
+    found
+}
+
+/// compute field constraints: remove candidates using avoidable rectangles
+///
+/// Only valid for puzzles with a unique solution.
+pub fn compute_avoidable_rectangles(
+    solver_state: &mut Sudoku<NumberSet>,
+    puzzle: &Sudoku<NumberSet>,
+) -> Vec<UniquenessPattern> {
+    let found = find_avoidable_rectangles(solver_state, puzzle);
+    solver_state.apply_eliminations(found.iter().flat_map(|p| &p.eliminations));
+    found
+}
+
+/// compute field constraints: solve the cell that breaks a BUG
+///
+/// Only valid for puzzles with a unique solution.
+pub fn compute_bug_plus_one(solver_state: &mut Sudoku<NumberSet>) -> Vec<UniquenessPattern> {
+    let found: Vec<UniquenessPattern> = find_bug_plus_one(solver_state).into_iter().collect();
+    solver_state.apply_eliminations(found.iter().flat_map(|p| &p.eliminations));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{Ix1, Ix2, Ix3, Ix4, Ix5, Ix6, Ix9};
+    use crate::examples::*;
+    use crate::strategy::{Pipeline, SolveOptions, StrategyKind};
+    use std::str::FromStr;
+
+    const N1: NumberSet = NumberSet::N1;
+    const N2: NumberSet = NumberSet::N2;
+    const N3: NumberSet = NumberSet::N3;
+    const N4: NumberSet = NumberSet::N4;
+    const N5: NumberSet = NumberSet::N5;
+
+    fn elimination(row: Ix, col: Ix, numbers: NumberSet) -> Elimination {
+        Elimination { row, col, numbers }
+    }
+
+    /// The unique rectangle of the given type on the corners r1c1, r1c4, r2c1 and r2c4
+    fn unique_rectangle(sudoku: &Sudoku<NumberSet>, kind: UniquenessKind) -> UniquenessPattern {
+        find_unique_rectangles(sudoku)
+            .into_iter()
+            .find(|p| p.kind == kind)
+            .unwrap()
+    }
+
+    /// An empty grid with the given candidates in r1c1, r1c4, r2c1 and r2c4
+    fn rectangle(corners: [NumberSet; 4]) -> Sudoku<NumberSet> {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        let cells = [(Ix1, Ix1), (Ix1, Ix4), (Ix2, Ix1), (Ix2, Ix4)];
+        for (&(r, c), &cell) in cells.iter().zip(corners.iter()) {
+            *sudoku.get_mut(r, c) = cell;
+        }
+        sudoku
+    }
+
+    fn eliminate_in_house(
+        sudoku: &mut Sudoku<NumberSet>,
+        house: House,
+        n: NumberSet,
+        keep: &[(Ix, Ix)],
+    ) {
+        for &(r, c) in house.cells().iter().filter(|cell| !keep.contains(cell)) {
+            sudoku.eliminate(r, c, n);
+        }
+    }
+
+    /// A BUG+1 in a solved grid: 1, 2 and 3 are left in eleven cells, each twice in a house
+    /// except for the 3 in the houses of r1c1
+    fn bug_plus_one() -> Sudoku<NumberSet> {
+        let solution =
+            "345617892618239457792458163426173589139582674587946231851394726964725318273861945";
+        let mut sudoku = Sudoku::from_line(solution).unwrap();
+        *sudoku.get_mut(Ix1, Ix1) = N1 | N2 | N3;
+        let pairs = [
+            (Ix1, Ix5, N1),
+            (Ix1, Ix9, N2),
+            (Ix5, Ix1, N1),
+            (Ix9, Ix1, N2),
+            (Ix2, Ix2, N1),
+            (Ix3, Ix3, N2),
+            (Ix2, Ix5, N1),
+            (Ix3, Ix9, N2),
+            (Ix5, Ix2, N1),
+            (Ix9, Ix3, N2),
+        ];
+        for &(r, c, n) in pairs.iter() {
+            *sudoku.get_mut(r, c) = N3 | n;
+        }
+        sudoku
+    }
+
+    #[test]
+    fn test_unique_rectangle_type1() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        *sudoku.get_mut(Ix1, Ix1) = N1 | N2;
+        *sudoku.get_mut(Ix1, Ix4) = N1 | N2;
+        *sudoku.get_mut(Ix2, Ix1) = N1 | N2;
+        *sudoku.get_mut(Ix2, Ix4) = N1 | N2 | N3;
+        let found = compute_unique_rectangles(&mut sudoku);
+        let ur = found
+            .iter()
+            .find(|p| p.kind == UniquenessKind::UniqueRectangle(1))
+            .unwrap();
+        assert_eq!(ur.name(), "Unique Rectangle Type 1");
+        assert_eq!(ur.numbers, N1 | N2);
+        assert_eq!(*sudoku.get(Ix2, Ix4), N3);
+    }
+
+    #[test]
+    fn test_unique_rectangle_type2() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        *sudoku.get_mut(Ix1, Ix1) = N1 | N2;
+        *sudoku.get_mut(Ix2, Ix1) = N1 | N2;
+        *sudoku.get_mut(Ix1, Ix4) = N1 | N2 | N3;
+        *sudoku.get_mut(Ix2, Ix4) = N1 | N2 | N3;
+        let found = find_unique_rectangles(&sudoku);
+        let ur = found
+            .iter()
+            .find(|p| p.kind == UniquenessKind::UniqueRectangle(2))
+            .unwrap();
+        // the rest of column 4 and of block 2
+        assert_eq!(ur.eliminations.len(), 13);
+        assert!(ur.eliminations.iter().all(|e| e.numbers == N3));
+    }
+
+    #[test]
+    fn test_avoidable_rectangle_type2() {
+        let puzzle = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        let mut sudoku = puzzle;
+        *sudoku.get_mut(Ix1, Ix1) = N1;
+        *sudoku.get_mut(Ix1, Ix4) = N2;
+        *sudoku.get_mut(Ix2, Ix1) = N2 | N5;
+        *sudoku.get_mut(Ix2, Ix4) = N1 | N5;
+        let found = find_avoidable_rectangles(&sudoku, &puzzle);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, UniquenessKind::AvoidableRectangle(2));
+        // the rest of row 2
+        assert_eq!(found[0].eliminations.len(), 7);
+        // nothing can be avoided if the solved corners were given
+        assert!(find_avoidable_rectangles(&sudoku, &sudoku).is_empty());
+    }
+
+    #[test]
+    fn test_unique_rectangle_type3() {
+        // the extras 3 and 4 of the roof form a naked pair with r3c4
+        let mut sudoku = rectangle([N1 | N2, N1 | N2 | N3, N1 | N2, N1 | N2 | N4]);
+        *sudoku.get_mut(Ix3, Ix4) = N3 | N4;
+        let ur = find_unique_rectangles(&sudoku)
+            .into_iter()
+            .find(|p| {
+                p.kind == UniquenessKind::UniqueRectangle(3)
+                    && p.eliminations.contains(&elimination(Ix9, Ix4, N3 | N4))
+            })
+            .unwrap();
+        // the rest of column 4
+        assert_eq!(ur.eliminations.len(), 6);
+    }
+
+    #[test]
+    fn test_unique_rectangle_type4() {
+        // 1 is confined to the roof in column 4, so the roof can't be 2
+        let mut sudoku = rectangle([N1 | N2, N1 | N2 | N3, N1 | N2, N1 | N2 | N4]);
+        let roof = [(Ix1, Ix4), (Ix2, Ix4)];
+        eliminate_in_house(&mut sudoku, House::col(Ix4), N1, &roof);
+        let ur = unique_rectangle(&sudoku, UniquenessKind::UniqueRectangle(4));
+        assert_eq!(
+            ur.eliminations,
+            vec![elimination(Ix1, Ix4, N2), elimination(Ix2, Ix4, N2)]
+        );
+    }
+
+    #[test]
+    fn test_unique_rectangle_type5() {
+        // one of the diagonal roof cells is 3
+        let sudoku = rectangle([N1 | N2, N1 | N2 | N3, N1 | N2 | N3, N1 | N2]);
+        let ur = unique_rectangle(&sudoku, UniquenessKind::UniqueRectangle(5));
+        assert_eq!(
+            ur.eliminations,
+            vec![
+                elimination(Ix1, Ix2, N3),
+                elimination(Ix1, Ix3, N3),
+                elimination(Ix2, Ix5, N3),
+                elimination(Ix2, Ix6, N3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unique_rectangle_type6() {
+        // 1 forms an X-Wing on the rows of the rectangle, so it is in the diagonal floor cells
+        let mut sudoku = rectangle([N1 | N2, N1 | N2 | N3, N1 | N2 | N4, N1 | N2]);
+        let corners = [(Ix1, Ix1), (Ix1, Ix4), (Ix2, Ix1), (Ix2, Ix4)];
+        eliminate_in_house(&mut sudoku, House::row(Ix1), N1, &corners);
+        eliminate_in_house(&mut sudoku, House::row(Ix2), N1, &corners);
+        let ur = unique_rectangle(&sudoku, UniquenessKind::UniqueRectangle(6));
+        assert_eq!(
+            ur.eliminations,
+            vec![elimination(Ix1, Ix4, N1), elimination(Ix2, Ix1, N1)]
+        );
+    }
+
+    #[test]
+    fn test_hidden_rectangle() {
+        // 1 is confined to the rectangle in row 2 and column 4, so r2c4 can't be 2
+        let mut sudoku = rectangle([N1 | N2, N1 | N2 | N3, N1 | N2 | N4, N1 | N2 | N5]);
+        let corners = [(Ix1, Ix1), (Ix1, Ix4), (Ix2, Ix1), (Ix2, Ix4)];
+        eliminate_in_house(&mut sudoku, House::row(Ix2), N1, &corners);
+        eliminate_in_house(&mut sudoku, House::col(Ix4), N1, &corners);
+        let hidden = unique_rectangle(&sudoku, UniquenessKind::HiddenRectangle);
+        assert_eq!(hidden.name(), "Hidden Rectangle");
+        assert_eq!(hidden.eliminations, vec![elimination(Ix2, Ix4, N2)]);
+    }
+
+    #[test]
+    fn test_avoidable_rectangle_type1() {
+        // r1c1 can't be 1, or the solved 1s and 2s could be swapped
+        let puzzle = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        let sudoku = rectangle([N1 | N3, N2, N2, N1]);
+        let found = find_avoidable_rectangles(&sudoku, &puzzle);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name(), "Avoidable Rectangle Type 1");
+        assert_eq!(found[0].eliminations, vec![elimination(Ix1, Ix1, N1)]);
+    }
+
+    #[test]
+    fn test_bug_plus_one() {
+        let mut sudoku = bug_plus_one();
+        let bug = find_bug_plus_one(&sudoku).unwrap();
+        assert_eq!(bug.cells, vec![(Ix1, Ix1)]);
+        assert_eq!(bug.numbers, N3);
+        compute_bug_plus_one(&mut sudoku);
+        assert_eq!(*sudoku.get(Ix1, Ix1), N3);
+        // without the +1 it's a deadly pattern, which isn't a BUG+1
+        assert!(find_bug_plus_one(&sudoku).is_none());
+    }
+
+    #[test]
+    fn test_uniqueness_needs_option() {
+        let puzzle = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        let mut type4 = rectangle([N1 | N2, N1 | N2 | N3, N1 | N2, N1 | N2 | N4]);
+        eliminate_in_house(&mut type4, House::col(Ix4), N1, &[(Ix1, Ix4), (Ix2, Ix4)]);
+        let grids = [
+            rectangle([N1 | N2, N1 | N2, N1 | N2, N1 | N2 | N3]),
+            rectangle([N1 | N2, N1 | N2 | N3, N1 | N2 | N3, N1 | N2]),
+            type4,
+            rectangle([N1 | N3, N2, N2, N1]),
+            bug_plus_one(),
+        ];
+        for &assume_uniqueness in [false, true].iter() {
+            let options = SolveOptions {
+                assume_uniqueness,
+                givens: Some(puzzle),
+            };
+            let mut pipeline = Pipeline::standard(options);
+            pipeline.remove_kind(StrategyKind::Forcing);
+            for grid in grids.iter() {
+                let mut sudoku = *grid;
+                let fired = pipeline
+                    .run(&mut sudoku)
+                    .iter()
+                    .any(|step| step.technique.contains("Rectangle") || step.technique == "BUG+1");
+                // nothing fires without the option, something on every grid with it
+                assert_eq!(fired, assume_uniqueness);
+            }
+        }
+    }
+}
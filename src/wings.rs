@@ -0,0 +1,262 @@
+//! Wings built from bivalue cells: XY-Wing, XYZ-Wing and W-Wing
+//!
+//! XY-Wing: a pivot {x,y} sees two pincers {x,z} and {y,z}. Whatever the pivot is, one of the
+//! pincers is z, so z can be removed from every cell that sees both pincers.
+//! XYZ-Wing: like the XY-Wing, but the pivot is {x,y,z}, so the eliminated cells also have to
+//! see the pivot.
+//! W-Wing: two pincers {x,y} are connected by a strong link on x (a house in which x can only
+//! go into two cells, one seeing each pincer). One of the pincers is y, so y can be removed
+//! from every cell that sees both pincers.
+
+use crate::base::{Elimination, Ix, NumberSet, Sudoku};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WingKind {
+    XY,
+    XYZ,
+    W,
+}
+
+/// A wing and the candidates it eliminates
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Wing {
+    pub kind: WingKind,
+    /// The pivot cell of an XY- or XYZ-Wing, or the two ends of the strong link that connects
+    /// the pincers of a W-Wing
+    pub pivot: Vec<(Ix, Ix)>,
+    pub pincers: [(Ix, Ix); 2],
+    /// The number that is eliminated
+    pub number: NumberSet,
+    pub eliminations: Vec<Elimination>,
+}
+
+fn cells_with_len(solver_state: &Sudoku<NumberSet>, len: usize) -> Vec<(Ix, Ix)> {
+    solver_state
+        .iter_with_index()
+        .filter(|(_, _, cell)| cell.len() == len)
+        .map(|(r, c, _)| (r, c))
+        .collect()
+}
+
+/// Eliminations of the number in all cells that see every one of the given cells
+fn eliminations_seeing(
+    solver_state: &Sudoku<NumberSet>,
+    cells: &[(Ix, Ix)],
+    number: NumberSet,
+) -> Vec<Elimination> {
+    solver_state
+        .iter_with_index()
+        .filter(|&(r, c, _)| cells.iter().all(|&cell| solver_state.sees((r, c), cell)))
+        .filter_map(|(r, c, _)| solver_state.elimination(r, c, number))
+        .collect()
+}
+
+/// Finds all XY-Wings that eliminate at least one candidate
+pub fn find_xy_wings(solver_state: &Sudoku<NumberSet>) -> Vec<Wing> {
+    let mut found = Vec::new();
+    let bivalue = cells_with_len(solver_state, 2);
+    for &pivot in bivalue.iter() {
+        let xy = *solver_state.get(pivot.0, pivot.1);
+        let pincers: Vec<(Ix, Ix)> = bivalue
+            .iter()
+            .cloned()
+            .filter(|&p| solver_state.sees(pivot, p))
+            .filter(|&p| (*solver_state.get(p.0, p.1) & xy).len() == 1)
+            .collect();
+        for (i, &p1) in pincers.iter().enumerate() {
+            for &p2 in pincers[i + 1..].iter() {
+                let (xz, yz) = (*solver_state.get(p1.0, p1.1), *solver_state.get(p2.0, p2.1));
+                let z = xz & yz;
+                // the pincers share z, which is not in the pivot, and cover both x and y
+                if !z.is_singleton() || z.intersects(xy) || (xz | yz) - z != xy {
+                    continue;
+                }
+                let eliminations = eliminations_seeing(solver_state, &[p1, p2], z);
+                if !eliminations.is_empty() {
+                    found.push(Wing {
+                        kind: WingKind::XY,
+                        pivot: vec![pivot],
+                        pincers: [p1, p2],
+                        number: z,
+                        eliminations,
+                    });
+                }
+            }
+        }
+    }
+    found
+}
+
+/// Finds all XYZ-Wings that eliminate at least one candidate
+pub fn find_xyz_wings(solver_state: &Sudoku<NumberSet>) -> Vec<Wing> {
+    let mut found = Vec::new();
+    let bivalue = cells_with_len(solver_state, 2);
+    for pivot in cells_with_len(solver_state, 3) {
+        let xyz = *solver_state.get(pivot.0, pivot.1);
+        let pincers: Vec<(Ix, Ix)> = bivalue
+            .iter()
+            .cloned()
+            .filter(|&p| solver_state.sees(pivot, p))
+            .filter(|&p| xyz.contains(*solver_state.get(p.0, p.1)))
+            .collect();
+        for (i, &p1) in pincers.iter().enumerate() {
+            for &p2 in pincers[i + 1..].iter() {
+                let (xz, yz) = (*solver_state.get(p1.0, p1.1), *solver_state.get(p2.0, p2.1));
+                let z = xz & yz;
+                if !z.is_singleton() || xz | yz != xyz {
+                    continue;
+                }
+                let eliminations = eliminations_seeing(solver_state, &[pivot, p1, p2], z);
+                if !eliminations.is_empty() {
+                    found.push(Wing {
+                        kind: WingKind::XYZ,
+                        pivot: vec![pivot],
+                        pincers: [p1, p2],
+                        number: z,
+                        eliminations,
+                    });
+                }
+            }
+        }
+    }
+    found
+}
+
+/// Finds all W-Wings that eliminate at least one candidate
+pub fn find_w_wings(solver_state: &Sudoku<NumberSet>) -> Vec<Wing> {
+    let mut found = Vec::new();
+    let bivalue = cells_with_len(solver_state, 2);
+    for (i, &p1) in bivalue.iter().enumerate() {
+        let xy = *solver_state.get(p1.0, p1.1);
+        for &p2 in bivalue[i + 1..].iter() {
+            // pincers that see each other would be a naked pair
+            if *solver_state.get(p2.0, p2.1) != xy || solver_state.sees(p1, p2) {
+                continue;
+            }
+            for x in xy.iter() {
+                let y = xy - x;
+                let eliminations = eliminations_seeing(solver_state, &[p1, p2], y);
+                if eliminations.is_empty() {
+                    continue;
+                }
+                let link = solver_state.houses().find_map(|house| {
+                    let cells: Vec<(Ix, Ix)> = house
+                        .cells()
+                        .iter()
+                        .cloned()
+                        .filter(|&(r, c)| solver_state.get(r, c).contains(x))
+                        .collect();
+                    match cells[..] {
+                        [a, b] if ![p1, p2].contains(&a) && ![p1, p2].contains(&b) => {
+                            if solver_state.sees(a, p1) && solver_state.sees(b, p2) {
+                                Some(vec![a, b])
+                            } else if solver_state.sees(b, p1) && solver_state.sees(a, p2) {
+                                Some(vec![b, a])
+                            } else {
+                                None
+                            }
+                        }
+                        _ => None,
+                    }
+                });
+                if let Some(link) = link {
+                    found.push(Wing {
+                        kind: WingKind::W,
+                        pivot: link,
+                        pincers: [p1, p2],
+                        number: y,
+                        eliminations,
+                    });
+                }
+            }
+        }
+    }
+    found
+}
+
+fn apply(solver_state: &mut Sudoku<NumberSet>, found: Vec<Wing>) -> Vec<Wing> {
+    solver_state.apply_eliminations(found.iter().flat_map(|w| &w.eliminations));
+    found
+}
+
+/// compute field constraints: remove candidates using XY-Wings
+pub fn compute_xy_wings(solver_state: &mut Sudoku<NumberSet>) -> Vec<Wing> {
+    let found = find_xy_wings(solver_state);
+    apply(solver_state, found)
+}
+
+/// compute field constraints: remove candidates using XYZ-Wings
+pub fn compute_xyz_wings(solver_state: &mut Sudoku<NumberSet>) -> Vec<Wing> {
+    let found = find_xyz_wings(solver_state);
+    apply(solver_state, found)
+}
+
+/// compute field constraints: remove candidates using W-Wings
+pub fn compute_w_wings(solver_state: &mut Sudoku<NumberSet>) -> Vec<Wing> {
+    let found = find_w_wings(solver_state);
+    apply(solver_state, found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{Ix1, Ix2, Ix3, Ix5, Ix7, Ix9};
+    use crate::examples::*;
+    use std::str::FromStr;
+
+    const N1: NumberSet = NumberSet::N1;
+    const N2: NumberSet = NumberSet::N2;
+    const N3: NumberSet = NumberSet::N3;
+
+    #[test]
+    fn test_xy_wing() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        *sudoku.get_mut(Ix1, Ix1) = N1 | N2;
+        *sudoku.get_mut(Ix1, Ix5) = N1 | N3;
+        *sudoku.get_mut(Ix5, Ix1) = N2 | N3;
+        let found = compute_xy_wings(&mut sudoku);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].pivot, vec![(Ix1, Ix1)]);
+        assert_eq!(found[0].pincers, [(Ix1, Ix5), (Ix5, Ix1)]);
+        assert_eq!(found[0].number, N3);
+        // r5c5 is the only cell seeing both pincers
+        assert_eq!(found[0].eliminations.len(), 1);
+        assert!(!sudoku.get(Ix5, Ix5).contains(N3));
+    }
+
+    #[test]
+    fn test_xyz_wing() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        *sudoku.get_mut(Ix1, Ix1) = N1 | N2 | N3;
+        *sudoku.get_mut(Ix1, Ix5) = N1 | N3;
+        *sudoku.get_mut(Ix2, Ix2) = N2 | N3;
+        let found = compute_xyz_wings(&mut sudoku);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, WingKind::XYZ);
+        // r1c2 and r1c3 see the pivot and both pincers
+        assert_eq!(found[0].eliminations.len(), 2);
+        assert!(!sudoku.get(Ix1, Ix3).contains(N3));
+        assert!(sudoku.get(Ix1, Ix7).contains(N3));
+    }
+
+    #[test]
+    fn test_w_wing() {
+        let mut sudoku = Sudoku::from_str(EMPTY_SUDOKU).unwrap();
+        *sudoku.get_mut(Ix1, Ix1) = N1 | N2;
+        *sudoku.get_mut(Ix5, Ix9) = N1 | N2;
+        // strong link on 1 in row 9 between r9c1 and r9c9
+        for c in Ix::all_indices().filter(|&c| c != Ix1 && c != Ix9) {
+            sudoku.eliminate(Ix9, c, N1);
+        }
+        let found = find_w_wings(&sudoku);
+        assert!(found.iter().all(|w| w.kind == WingKind::W));
+        let wing = found.iter().find(|w| w.number == N2).unwrap();
+        assert_eq!(wing.pivot, vec![(Ix9, Ix1), (Ix9, Ix9)]);
+        let mut eliminated: Vec<_> = wing.eliminations.iter().map(|e| (e.row, e.col)).collect();
+        eliminated.sort();
+        assert_eq!(eliminated, vec![(Ix1, Ix9), (Ix5, Ix1)]);
+        compute_w_wings(&mut sudoku);
+        assert!(!sudoku.get(Ix1, Ix9).contains(N2));
+        assert!(sudoku.get(Ix3, Ix9).contains(N2));
+    }
+}